@@ -1,7 +1,7 @@
 use bevy::{prelude::*, window::close_on_esc};
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
 use bevy_saiko_ui::{
-    common::{bounds::Bounds, value::{Percent, Value}}, render::font::sdf::SaikoFontSdf, ui::{component::rect::RectComponent, node::SaikoNode, position::RelativePosition}, SaikoUiPlugin
+    common::{bounds::Bounds, value::{Percent, Value}}, render::{font::sdf::SaikoFontSdf, SaikoCamera}, ui::{component::rect::RectComponent, node::SaikoNode, position::RelativePosition}, SaikoUiPlugin
 };
 
 #[derive(Resource)]
@@ -53,10 +53,13 @@ fn setup(
     });
 
     // camera
-    commands.spawn(Camera3dBundle {
-        transform: Transform::from_xyz(-2.5, 4.5, 9.0).looking_at(Vec3::ZERO, Vec3::Y),
-        ..default()
-    });
+    commands.spawn((
+        Camera3dBundle {
+            transform: Transform::from_xyz(-2.5, 4.5, 9.0).looking_at(Vec3::ZERO, Vec3::Y),
+            ..default()
+        },
+        SaikoCamera::default(),
+    ));
 
     commands.spawn((
         SaikoNode::new(RelativePosition::Align(Percent::new(0.5), Percent::new(0.5), Value::Px(200.0), Value::Px(200.0))),
@@ -44,4 +44,17 @@ fn get_all_children_r (
         ancestors.append(&mut get_all_children_r(*entity, entities));
     }
     ancestors
-}
\ No newline at end of file
+}
+
+//==============================================================================
+//          find_ancestor function
+//==============================================================================
+
+///This function returns the closest entity that matches, starting with the given entity and then walking up its parents.
+pub fn find_ancestor(entity : Entity, parents : &Query<&Parent>, mut matches : impl FnMut(Entity) -> bool) -> Option<Entity> {
+    let mut current_entity = entity;
+    loop {
+        if matches(current_entity) { return Some(current_entity) }
+        current_entity = **parents.get(current_entity).ok()?;
+    }
+}
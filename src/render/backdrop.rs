@@ -0,0 +1,346 @@
+//==============================================================================
+//  Backdrop blur shows a blurred copy of the scene behind a rect. The ui is
+//  only redrawn when it changes, but the scene behind it changes every frame,
+//  so the backdrops are kept per view and drawn again every frame, right
+//  before the ui is composited over the scene.
+//==============================================================================
+
+use bevy::{
+    core_pipeline::fullscreen_vertex_shader::fullscreen_shader_vertex_state,
+    prelude::*,
+    render::{
+        render_resource::{
+            binding_types::{sampler, storage_buffer_read_only, texture_2d, uniform_buffer},
+            BindGroup, BindGroupEntries, BindGroupLayout, BindGroupLayoutEntries, BlendState,
+            CachedRenderPipelineId, ColorTargetState, ColorWrites, Extent3d, FilterMode,
+            FragmentState, LoadOp, MultisampleState, Operations, PipelineCache, PrimitiveState,
+            RenderPassColorAttachment, RenderPassDescriptor, RenderPipelineDescriptor, Sampler,
+            SamplerBindingType, SamplerDescriptor, ShaderStages, StorageBuffer, StoreOp,
+            TextureDescriptor, TextureDimension, TextureFormat, TextureSampleType, TextureUsages,
+            TextureView, TextureViewDescriptor, UniformBuffer, VertexState,
+        },
+        renderer::{RenderContext, RenderDevice, RenderQueue},
+        texture::BevyDefault,
+        view::ViewTarget,
+        Render, RenderApp, RenderSet,
+    },
+    utils::HashMap,
+};
+
+use super::{buffer::{BackdropBuffer, ClipBuffer}, BACKDROP_SHADER_HANDLE};
+
+//==============================================================================
+//             SaikoBackdropPlugin
+//==============================================================================
+
+pub struct SaikoBackdropPlugin;
+
+impl Plugin for SaikoBackdropPlugin {
+    fn build(&self, _app: &mut App) {}
+
+    fn finish(&self, app: &mut App) {
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app
+            .init_resource::<SaikoBackdrops>()
+            .add_systems(Render, prune_backdrop_views.in_set(RenderSet::PrepareResources));
+    }
+}
+
+/// The backdrops of a view are kept until the ui changes, so views that are no longer rendered
+/// are dropped here along with their mip chain.
+fn prune_backdrop_views(
+    mut backdrops : ResMut<SaikoBackdrops>,
+    views : Query<(), With<ViewTarget>>,
+) {
+    backdrops.views.retain(|view_entity, _| views.contains(*view_entity));
+}
+
+//==============================================================================
+//             SaikoBackdrops
+//==============================================================================
+
+/// The most times the scene is halved for blurring. Each level doubles the blur radius it can show.
+const BACKDROP_MIP_LEVELS : u32 = 6;
+
+/// The backdrops of every view, and the pipelines that blur and draw them.
+#[derive(Resource)]
+pub(crate) struct SaikoBackdrops {
+    views : HashMap<Entity, SaikoViewBackdrops>,
+    downsample_pipeline : CachedRenderPipelineId,
+    backdrop_pipeline : CachedRenderPipelineId,
+    downsample_bind_group_layout : BindGroupLayout,
+    backdrop_bind_group_layout : BindGroupLayout,
+    sampler : Sampler,
+}
+
+/// The backdrops of a single view, and the mip chain the scene is blurred into.
+struct SaikoViewBackdrops {
+    count : u32,
+    /// Every level of the mip chain, which is sampled by the backdrops.
+    texture_view : TextureView,
+    /// One view per level, so each level can be rendered to.
+    mip_views : Vec<TextureView>,
+    bind_group : BindGroup,
+    width : u32,
+    height : u32,
+}
+
+impl SaikoBackdrops {
+    /// Stores the backdrops of the view, so they can be drawn every frame until the ui changes.
+    pub(crate) fn prepare_view(
+        &mut self,
+        view_entity : Entity,
+        backdrops : &[BackdropBuffer],
+        clips : &[ClipBuffer],
+        width : u32,
+        height : u32,
+        scale_factor : f32,
+        render_device : &RenderDevice,
+        render_queue : &RenderQueue,
+    ) {
+        if backdrops.is_empty() {
+            self.views.remove(&view_entity);
+            return;
+        }
+
+        let (texture_view, mip_views) = match self.views.remove(&view_entity) {
+            Some(view) if view.width == width && view.height == height => (view.texture_view, view.mip_views),
+            _ => Self::create_mip_chain(view_entity, width, height, render_device),
+        };
+
+        let mut backdrop_buffer = StorageBuffer::from(backdrops.to_vec());
+        backdrop_buffer.write_buffer(render_device, render_queue);
+        let mut screen_size = UniformBuffer::from(Vec2::new(width as f32, height as f32));
+        screen_size.write_buffer(render_device, render_queue);
+        let mut scale_factor = UniformBuffer::from(scale_factor);
+        scale_factor.write_buffer(render_device, render_queue);
+        // The clips are only created once a clip is pushed, but the shader always needs the placeholder.
+        let mut clip_buffer = match clips.is_empty() {
+            true => StorageBuffer::from(vec![ClipBuffer::default()]),
+            false => StorageBuffer::from(clips.to_vec()),
+        };
+        clip_buffer.write_buffer(render_device, render_queue);
+
+        let bind_group = render_device.create_bind_group(
+            "saiko_backdrop_bind_group",
+            &self.backdrop_bind_group_layout,
+            &BindGroupEntries::sequential((
+                backdrop_buffer.binding().unwrap(),
+                screen_size.binding().unwrap(),
+                &texture_view,
+                &self.sampler,
+                scale_factor.binding().unwrap(),
+                clip_buffer.binding().unwrap(),
+            )),
+        );
+
+        self.views.insert(view_entity, SaikoViewBackdrops {
+            count : backdrops.len() as u32,
+            texture_view,
+            mip_views,
+            bind_group,
+            width,
+            height,
+        });
+    }
+
+    /// Blurs what has been rendered to the view so far, and draws it inside of every backdrop.
+    pub(crate) fn render(
+        &self,
+        view_entity : Entity,
+        view_target : &ViewTarget,
+        pipeline_cache : &PipelineCache,
+        render_context : &mut RenderContext,
+    ) {
+        let Some(view) = self.views.get(&view_entity) else { return };
+        let (Some(downsample_pipeline), Some(backdrop_pipeline)) = (
+            pipeline_cache.get_render_pipeline(self.downsample_pipeline),
+            pipeline_cache.get_render_pipeline(self.backdrop_pipeline),
+        ) else {
+            return;
+        };
+
+        // Every level is half the size of the one before it, so sampling a higher level is a wider blur.
+        for (level, target) in view.mip_views.iter().enumerate() {
+            let source = match level {
+                0 => view_target.main_texture_view(),
+                _ => &view.mip_views[level - 1],
+            };
+
+            let bind_group = render_context.render_device().create_bind_group(
+                "saiko_backdrop_downsample_bind_group",
+                &self.downsample_bind_group_layout,
+                &BindGroupEntries::with_indices(((2, source), (3, &self.sampler))),
+            );
+
+            let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+                label: "SaikoUI Backdrop Downsample Pass".into(),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: target,
+                    resolve_target: None,
+                    ops: Operations { load: LoadOp::Clear(Default::default()), store: StoreOp::Store },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            render_pass.set_render_pipeline(downsample_pipeline);
+            render_pass.set_bind_group(0, &bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+            label: "SaikoUI Backdrop Pass".into(),
+            color_attachments: &[Some(view_target.get_unsampled_color_attachment())],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        render_pass.set_render_pipeline(backdrop_pipeline);
+        render_pass.set_bind_group(0, &view.bind_group, &[]);
+        render_pass.draw(0..6, 0..view.count);
+    }
+
+    /// Creates the mip chain the scene is blurred into, starting at half the size of the view.
+    fn create_mip_chain(
+        view_entity : Entity,
+        width : u32,
+        height : u32,
+        render_device : &RenderDevice,
+    ) -> (TextureView, Vec<TextureView>) {
+        let size = UVec2::new(width / 2, height / 2).max(UVec2::ONE);
+        let mip_level_count = (size.min_element().ilog2() + 1).min(BACKDROP_MIP_LEVELS);
+
+        let texture = render_device.create_texture(&TextureDescriptor {
+            label: Some(format!("SaikoUI Backdrop Texture {:?}", view_entity).as_str()),
+            size: Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::bevy_default(),
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+
+        let texture_view = texture.create_view(&TextureViewDescriptor {
+            label: Some("SaikoUI Backdrop View"),
+            ..Default::default()
+        });
+
+        let mip_views = (0..mip_level_count)
+            .map(|level| texture.create_view(&TextureViewDescriptor {
+                label: Some("SaikoUI Backdrop Mip View"),
+                base_mip_level: level,
+                mip_level_count: Some(1),
+                ..Default::default()
+            }))
+            .collect();
+
+        (texture_view, mip_views)
+    }
+}
+
+impl FromWorld for SaikoBackdrops {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+
+        let downsample_bind_group_layout = render_device.create_bind_group_layout(
+            "saiko_backdrop_downsample_bind_group_layout",
+            &BindGroupLayoutEntries::with_indices(
+                ShaderStages::FRAGMENT,
+                (
+                    (2, texture_2d(TextureSampleType::Float { filterable: true })),
+                    (3, sampler(SamplerBindingType::Filtering)),
+                ),
+            ),
+        );
+
+        let backdrop_bind_group_layout = render_device.create_bind_group_layout(
+            "saiko_backdrop_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::VERTEX_FRAGMENT,
+                (
+                    storage_buffer_read_only::<Vec<BackdropBuffer>>(false),
+                    uniform_buffer::<Vec2>(false),
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    sampler(SamplerBindingType::Filtering),
+                    uniform_buffer::<f32>(false),
+                    storage_buffer_read_only::<Vec<ClipBuffer>>(false),
+                ),
+            ),
+        );
+
+        let sampler = render_device.create_sampler(&SamplerDescriptor {
+            label: Some("SaikoUI Backdrop Sampler"),
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            mipmap_filter: FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let pipeline_cache = world.resource::<PipelineCache>();
+
+        let downsample_pipeline = pipeline_cache.queue_render_pipeline(RenderPipelineDescriptor {
+            label: Some("SaikoUI Backdrop Downsample Pipeline".into()),
+            layout: vec![downsample_bind_group_layout.clone()],
+            vertex: fullscreen_shader_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: BACKDROP_SHADER_HANDLE,
+                shader_defs: vec![],
+                entry_point: "downsample_fragment".into(),
+                targets: vec![Some(ColorTargetState {
+                    format: TextureFormat::bevy_default(),
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState::default(),
+            multisample: MultisampleState::default(),
+            push_constant_ranges: vec![],
+            depth_stencil: None,
+        });
+
+        let backdrop_pipeline = pipeline_cache.queue_render_pipeline(RenderPipelineDescriptor {
+            label: Some("SaikoUI Backdrop Pipeline".into()),
+            layout: vec![backdrop_bind_group_layout.clone()],
+            vertex: VertexState {
+                shader: BACKDROP_SHADER_HANDLE,
+                shader_defs: vec![],
+                entry_point: "backdrop_vertex".into(),
+                buffers: vec![],
+            },
+            fragment: Some(FragmentState {
+                shader: BACKDROP_SHADER_HANDLE,
+                shader_defs: vec![],
+                entry_point: "backdrop_fragment".into(),
+                targets: vec![Some(ColorTargetState {
+                    format: TextureFormat::bevy_default(),
+                    blend: Some(BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState::default(),
+            multisample: MultisampleState::default(),
+            push_constant_ranges: vec![],
+            depth_stencil: None,
+        });
+
+        Self {
+            views : HashMap::new(),
+            downsample_pipeline,
+            backdrop_pipeline,
+            downsample_bind_group_layout,
+            backdrop_bind_group_layout,
+            sampler,
+        }
+    }
+}
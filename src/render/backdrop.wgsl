@@ -0,0 +1,140 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+struct Bound {
+    center : vec2<f32>,
+    size : vec2<f32>,
+    z_index : i32,
+}
+
+struct Backdrop {
+    bound : Bound,
+    border_radius : vec4<f32>,
+    blur_radius : f32,
+    opacity : f32,
+    clip : u32,
+}
+
+struct Clip {
+    bound : Bound,
+    border_radius : vec4<f32>,
+    parent : u32,
+}
+
+struct VertexOutput {
+    @builtin(position) position : vec4<f32>,
+    // The position of the fragment in pixels, relative to the center of the screen with y pointing down.
+    @location(0) point : vec2<f32>,
+    @location(1) @interpolate(flat) instance : u32,
+}
+
+@group(0) @binding(0)
+var<storage, read> backdrops : array<Backdrop>;
+@group(0) @binding(1)
+var<uniform> resolution : vec2<f32>;
+// While downsampling this is the level above the one being drawn, otherwise it is the whole mip chain.
+@group(0) @binding(2)
+var blur_texture : texture_2d<f32>;
+@group(0) @binding(3)
+var blur_sampler : sampler;
+// The backdrops are in logical pixels, which are this many physical pixels.
+@group(0) @binding(4)
+var<uniform> scale_factor : f32;
+@group(0) @binding(5)
+var<storage, read> clips : array<Clip>;
+
+//==============================================================================
+//             Downsample
+//==============================================================================
+
+// The dual kawase downsample. Each corner sample is filtered between four texels, so
+// together they blur over a 4x4 area of the level above.
+@fragment
+fn downsample_fragment(in : FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    var texel = 1.0 / vec2<f32>(textureDimensions(blur_texture));
+    var color = textureSample(blur_texture, blur_sampler, in.uv) * 4.0;
+    color += textureSample(blur_texture, blur_sampler, in.uv + vec2<f32>(-texel.x, -texel.y));
+    color += textureSample(blur_texture, blur_sampler, in.uv + vec2<f32>(texel.x, -texel.y));
+    color += textureSample(blur_texture, blur_sampler, in.uv + vec2<f32>(-texel.x, texel.y));
+    color += textureSample(blur_texture, blur_sampler, in.uv + vec2<f32>(texel.x, texel.y));
+    return color / 8.0;
+}
+
+//==============================================================================
+//             Backdrop
+//==============================================================================
+
+@vertex
+fn backdrop_vertex(
+    @builtin(vertex_index) vertex_index : u32,
+    @builtin(instance_index) instance_index : u32,
+) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, 1.0),
+    );
+    
+    var bound = backdrops[instance_index].bound;
+    var position = bound.center + corners[vertex_index] * (bound.size + 1.0);
+    
+    var out : VertexOutput;
+    out.position = vec4<f32>(position * scale_factor / (resolution * 0.5), 0.0, 1.0);
+    out.point = position * vec2<f32>(1.0, -1.0);
+    out.instance = instance_index;
+    return out;
+}
+
+@fragment
+fn backdrop_fragment(in : VertexOutput) -> @location(0) vec4<f32> {
+    var backdrop = backdrops[in.instance];
+    var uv = in.position.xy / resolution;
+    
+    // The first level is half the size of the view, so it is already blurred by two pixels.
+    var max_level = f32(textureNumLevels(blur_texture) - 1u);
+    var level = clamp(log2(max(backdrop.blur_radius * scale_factor, 1.0)) - 1.0, 0.0, max_level);
+    
+    // A small tent filter on top of the chosen level hides the blockiness of the smaller levels.
+    var texel = exp2(level) / vec2<f32>(textureDimensions(blur_texture)) * 0.5;
+    var color = textureSampleLevel(blur_texture, blur_sampler, uv + vec2<f32>(-texel.x, -texel.y), level);
+    color += textureSampleLevel(blur_texture, blur_sampler, uv + vec2<f32>(texel.x, -texel.y), level);
+    color += textureSampleLevel(blur_texture, blur_sampler, uv + vec2<f32>(-texel.x, texel.y), level);
+    color += textureSampleLevel(blur_texture, blur_sampler, uv + vec2<f32>(texel.x, texel.y), level);
+    color /= 4.0;
+    
+    var shape = coverage(rounded_box_sdf(in.point, backdrop.bound, backdrop.border_radius));
+    return vec4<f32>(color.rgb, 1.0) * shape * backdrop.opacity * clip_coverage(in.point, backdrop.clip);
+}
+
+// How much of the pixel is inside the clip, and every clip it is nested in. A clip index of
+// 0 means the backdrop is not clipped.
+fn clip_coverage(point : vec2<f32>, clip_index : u32) -> f32 {
+    var result = 1.0;
+    var index = clip_index;
+    loop {
+        if (index == 0u) {
+            break;
+        }
+        
+        var clip = clips[index];
+        result = min(result, coverage(rounded_box_sdf(point, clip.bound, clip.border_radius)));
+        index = clip.parent;
+    }
+    return result;
+}
+
+fn coverage(distance : f32) -> f32 {
+    return clamp(0.5 - distance * scale_factor, 0.0, 1.0);
+}
+
+fn rounded_box_sdf(point : vec2<f32>, bound : Bound, border_radius : vec4<f32>) -> f32 {
+    var size = bound.size;
+    var p = point - bound.center * vec2<f32>(1.0, -1.0);
+    var r = select(border_radius.xy, border_radius.zw, p.x > 0.0);
+    r = select(r, r.yy, p.y > 0.0);
+    r = min(r, size);
+    var q = abs(p) - size + r.x;
+    return min(max(q.x, q.y), 0.0) + length(max(q, vec2<f32>(0.0, 0.0))) - r.x;
+}
@@ -6,4 +6,10 @@
 @fragment
 fn fs_main(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
     return textureSample(in_texture, in_sampler, in.uv);
-}
\ No newline at end of file
+}
+
+@fragment
+fn fs_premultiply(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    var color = textureSample(in_texture, in_sampler, in.uv);
+    return vec4<f32>(color.rgb * color.a, color.a);
+}
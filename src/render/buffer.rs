@@ -2,10 +2,12 @@ use bevy::{
     ecs::storage,
     math::{Vec2, Vec3, Vec4},
     prelude::*,
-    render::{render_resource::{AsBindGroup, PreparedBindGroup, ShaderType, Texture, TextureId, TextureView}, texture},
+    render::{render_resource::{AsBindGroup, BindGroup, PreparedBindGroup, ShaderType, Texture, TextureId, TextureView}, texture},
 };
 
-use crate::common::bounds::Bounds;
+use std::ops::Range;
+
+use crate::{common::{bounds::Bounds, value::Value}, render::{font::sdf::{SaikoFontSdf, SaikoGPUFontAtlas}, image::SaikoGPUImageAtlas, path::SaikoPath, svg::{SaikoSvg, SaikoSvgIcons}}};
 
 //==============================================================================
 //             Saikobuffer
@@ -15,37 +17,348 @@ use crate::common::bounds::Bounds;
 pub struct SaikoBuffer {
     #[storage(0, read_only)]
     pub rectangles: Vec<RectBuffer>,
-    // #[texture(1, dimension = "2d_array", visibility(fragment))]
-    // #[sampler(2)]
-    // pub fonts : Handle<Image>,
     #[uniform(1)]
     pub screen_size: Vec2,
+    #[storage(2, read_only)]
+    pub glyphs: Vec<GlyphBuffer>,
+    #[storage(3, read_only)]
+    pub image_quads: Vec<ImageQuadBuffer>,
+    /// Every clip that has been pushed. The first clip is a placeholder, so primitives with a clip
+    /// index of 0 are not clipped.
+    #[storage(4, read_only)]
+    pub clips: Vec<ClipBuffer>,
+    /// The layers that are drawn into their own texture. Layer `n` is drawn into the texture of
+    /// target `n + 1`, since target 0 is the ui's render texture.
+    #[storage(5, read_only)]
+    pub layers: Vec<LayerBuffer>,
+    #[storage(6, read_only)]
+    pub paths: Vec<PathBuffer>,
+    /// The curves and joins of every path. Each path points to its own run of segments.
+    #[storage(7, read_only)]
+    pub path_segments: Vec<PathSegmentBuffer>,
+    #[storage(8, read_only)]
+    pub ellipses: Vec<EllipseBuffer>,
+    /// How many physical pixels there are in a logical pixel. Everything in the buffer is in logical
+    /// pixels, and the shader scales it to the physical `screen_size`. This is set by the target the
+    /// buffer is drawn into.
+    #[uniform(9)]
+    pub scale_factor: f32,
+    /// Text that still needs to be laid out into glyphs. This happens in the render world once the
+    /// font atlas is available.
+    pub texts: Vec<TextBuffer>,
+    /// Images that still need to be fit into their bounds. This happens in the render world once
+    /// the image is in the image atlas.
+    pub images: Vec<ImageBuffer>,
+    /// Icons that still need to be turned into paths. This happens in the render world once the
+    /// svg has been extracted.
+    pub icons: Vec<IconBuffer>,
+    /// The rects that blur the scene behind them. These are drawn straight onto the view every
+    /// frame, under the ui. See [`crate::render::backdrop`].
+    pub backdrops: Vec<BackdropBuffer>,
+    /// Nine-slice fills that still need to be placed in the image atlas, with the index of the
+    /// rect they fill.
+    pub nine_slices: Vec<(usize, NineSlice)>,
+    /// The draw calls that render the buffer, sorted by z_index. These are built by [`SaikoBuffer::prepare`].
+    pub batches: Vec<SaikoBatch>,
+    /// The target each primitive is drawn into, in the order they were pushed.
+    draw_order: Vec<(u32, SaikoDrawCommand)>,
+    clip_stack: Vec<u32>,
+    layer_stack: Vec<u32>,
+    is_prepared: bool,
 }
 
 impl SaikoBuffer {
-    pub const NUMBER_OF_ENTRIES: u32 = 2;
+    pub const NUMBER_OF_ENTRIES: u32 = 10;
+
+    /// An empty buffer for a target with the given number of physical pixels per logical pixel.
+    pub fn new(scale_factor: f32) -> Self {
+        Self { scale_factor, ..Default::default() }
+    }
     
     pub fn push_rect(&mut self, rect: impl Into<RectBuffer>) {
-        self.rectangles.push(rect.into())
+        let mut rect = rect.into();
+        rect.clip = self.current_clip();
+        self.push_command(SaikoDrawCommand::Rect(self.rectangles.len()));
+        self.rectangles.push(rect)
+    }
+    
+    pub fn push_text(&mut self, text: impl Into<TextBuffer>) {
+        let mut text = text.into();
+        text.clip = self.current_clip();
+        self.push_command(SaikoDrawCommand::Text(self.texts.len()));
+        self.texts.push(text)
+    }
+    
+    pub fn push_nine_slice_rect(&mut self, rect: impl Into<RectBuffer>, nine_slice: NineSlice) {
+        self.nine_slices.push((self.rectangles.len(), nine_slice));
+        self.push_rect(rect)
+    }
+    
+    pub fn push_image(&mut self, image: impl Into<ImageBuffer>) {
+        let mut image = image.into();
+        image.clip = self.current_clip();
+        self.push_command(SaikoDrawCommand::Image(self.images.len()));
+        self.images.push(image)
+    }
+    
+    pub fn push_icon(&mut self, icon: impl Into<IconBuffer>) {
+        let mut icon = icon.into();
+        icon.clip = self.current_clip();
+        self.push_command(SaikoDrawCommand::Icon(self.icons.len()));
+        self.icons.push(icon)
+    }
+    
+    pub fn push_ellipse(&mut self, ellipse: impl Into<EllipseBuffer>) {
+        let mut ellipse = ellipse.into();
+        ellipse.clip = self.current_clip();
+        self.push_command(SaikoDrawCommand::Ellipse(self.ellipses.len()));
+        self.ellipses.push(ellipse)
+    }
+    
+    /// Pushes the path, with its points relative to the center of the path's bounds.
+    pub fn push_path(&mut self, mut path: PathBuffer, outline: &SaikoPath, line_cap: LineCap, line_join: LineJoin) {
+        if outline.is_empty() { return }
+        
+        outline.push_segments(path.bound.center, &mut path, line_cap, line_join, &mut self.path_segments);
+        path.clip = self.current_clip();
+        self.push_command(SaikoDrawCommand::Path(self.paths.len()));
+        self.paths.push(path);
+    }
+    
+    /// Backdrops are drawn under the whole ui, so the clip and the opacity of every layer they are
+    /// inside of are recorded on the backdrop instead of in the draw order.
+    pub fn push_backdrop(&mut self, backdrop: impl Into<BackdropBuffer>) {
+        let mut backdrop = backdrop.into();
+        backdrop.clip = self.current_clip();
+        backdrop.opacity = self.layer_stack.iter()
+            .map(|target| self.layers[*target as usize - 1].opacity)
+            .product();
+        self.backdrops.push(backdrop)
+    }
+    
+    /// Clips everything that is pushed until [`SaikoBuffer::pop_clip`] to the rounded box. Clips
+    /// that are pushed inside of another clip are clipped by both.
+    pub fn push_clip(&mut self, bound: Bounds, border_radius: Vec4) {
+        if self.clips.is_empty() {
+            self.clips.push(ClipBuffer::default());
+        }
+        
+        self.clips.push(ClipBuffer {
+            bound,
+            border_radius,
+            parent : self.current_clip(),
+        });
+        self.clip_stack.push(self.clips.len() as u32 - 1);
+    }
+    
+    pub fn pop_clip(&mut self) {
+        self.clip_stack.pop();
+    }
+    
+    /// The index of the clip that new primitives are clipped by.
+    pub fn current_clip(&self) -> u32 {
+        self.clip_stack.last().copied().unwrap_or(0)
+    }
+    
+    /// Draws everything that is pushed until [`SaikoBuffer::pop_layer`] into its own texture. The
+    /// texture is drawn back into the current target with a single opacity.
+    pub fn push_layer(&mut self, bound: Bounds, opacity: f32) {
+        self.push_command(SaikoDrawCommand::Layer(self.layers.len()));
+        self.layers.push(LayerBuffer {
+            bound,
+            opacity : opacity.clamp(0.0, 1.0),
+            clip : self.current_clip(),
+        });
+        self.layer_stack.push(self.layers.len() as u32);
+    }
+    
+    pub fn pop_layer(&mut self) {
+        self.layer_stack.pop();
+    }
+    
+    /// The target that new primitives are drawn into. 0 is the ui's render texture, and every
+    /// other target is a layer.
+    pub fn current_target(&self) -> u32 {
+        self.layer_stack.last().copied().unwrap_or(0)
+    }
+    
+    fn push_command(&mut self, command: SaikoDrawCommand) {
+        self.draw_order.push((self.current_target(), command));
+    }
+    
+    /// Every image that is drawn by this buffer.
+    pub(crate) fn image_ids(&self) -> impl Iterator<Item = &AssetId<Image>> {
+        self.images
+            .iter()
+            .map(|image| &image.image)
+            .chain(self.nine_slices.iter().map(|(_, nine_slice)| &nine_slice.image))
+    }
+    
+    /// Lays out the text of the buffer, and sorts every primitive by its z_index. Primitives that share
+    /// a z_index keep the order they were pushed in. Runs of the same primitive are then grouped into 
+    /// batches so they can be drawn with a single draw call.
+    pub(crate) fn prepare(&mut self, font_atlas : &SaikoGPUFontAtlas, image_atlas : &SaikoGPUImageAtlas, icons : &SaikoSvgIcons) {
+        if self.is_prepared { return }
+        
+        for (index, nine_slice) in &self.nine_slices {
+            let rect = &mut self.rectangles[*index];
+            match image_atlas.layout_nine_slice(nine_slice) {
+                Some(buffer) => rect.nine_slice = buffer,
+                // The image is still loading, so nothing is drawn until it is ready.
+                None => {
+                    rect.fill_style.fill_type = FillType::Solid as u32;
+                    rect.fill_style.fill_color = Color::NONE;
+                },
+            }
+        }
+        
+        let mut glyphs = Vec::new();
+        let mut image_quads = Vec::new();
+        // Icons are drawn as paths, so their paths go after the ones that were pushed directly.
+        let mut paths = std::mem::take(&mut self.paths);
+        let mut path_segments = std::mem::take(&mut self.path_segments);
+        let mut draws = self.draw_order
+            .iter()
+            .map(|(target, command)| match *command {
+                SaikoDrawCommand::Rect(index) => 
+                    (*target, self.rectangles[index].bound.z_index, SaikoPrimitive::Rect, index..index + 1),
+                SaikoDrawCommand::Text(index) => {
+                    let start = glyphs.len();
+                    font_atlas.layout_text(&self.texts[index], &mut glyphs);
+                    (*target, self.texts[index].bound.z_index, SaikoPrimitive::Glyph, start..glyphs.len())
+                },
+                SaikoDrawCommand::Image(index) => {
+                    let start = image_quads.len();
+                    image_atlas.layout_image(&self.images[index], &mut image_quads);
+                    (*target, self.images[index].bound.z_index, SaikoPrimitive::Image, start..image_quads.len())
+                },
+                SaikoDrawCommand::Layer(index) =>
+                    (*target, self.layers[index].bound.z_index, SaikoPrimitive::Layer, index..index + 1),
+                SaikoDrawCommand::Path(index) =>
+                    (*target, paths[index].bound.z_index, SaikoPrimitive::Path, index..index + 1),
+                SaikoDrawCommand::Icon(index) => {
+                    let start = paths.len();
+                    icons.layout_icon(&self.icons[index], &mut paths, &mut path_segments);
+                    (*target, self.icons[index].bound.z_index, SaikoPrimitive::Path, start..paths.len())
+                },
+                SaikoDrawCommand::Ellipse(index) =>
+                    (*target, self.ellipses[index].bound.z_index, SaikoPrimitive::Ellipse, index..index + 1),
+            })
+            .collect::<Vec<_>>();
+        
+        // Layers are always pushed after the target they are drawn into, so drawing the targets
+        // from last to first finishes every layer before it is used. sort_by_key is stable, which
+        // keeps the push order for ties.
+        draws.sort_by_key(|(target, z_index, _, _)| (std::cmp::Reverse(*target), *z_index));
+        
+        self.path_segments = path_segments;
+        let rectangles = std::mem::take(&mut self.rectangles);
+        let ellipses = std::mem::take(&mut self.ellipses);
+        self.glyphs.clear();
+        self.image_quads.clear();
+        self.batches.clear();
+        
+        for (target, _, primitive, range) in draws {
+            if range.is_empty() { continue }
+            
+            let start = match primitive {
+                SaikoPrimitive::Rect => {
+                    self.rectangles.extend_from_slice(&rectangles[range.clone()]);
+                    self.rectangles.len() - range.len()
+                },
+                SaikoPrimitive::Glyph => {
+                    self.glyphs.extend_from_slice(&glyphs[range.clone()]);
+                    self.glyphs.len() - range.len()
+                },
+                SaikoPrimitive::Image => {
+                    self.image_quads.extend_from_slice(&image_quads[range.clone()]);
+                    self.image_quads.len() - range.len()
+                },
+                SaikoPrimitive::Path => {
+                    self.paths.extend_from_slice(&paths[range.clone()]);
+                    self.paths.len() - range.len()
+                },
+                SaikoPrimitive::Ellipse => {
+                    self.ellipses.extend_from_slice(&ellipses[range.clone()]);
+                    self.ellipses.len() - range.len()
+                },
+                // Each layer samples its own texture, so they are left in place and never batched.
+                SaikoPrimitive::Layer => range.start,
+            };
+            let end = (start + range.len()) as u32;
+            
+            match self.batches.last_mut() {
+                Some(batch) if batch.primitive == primitive && batch.target == target && primitive != SaikoPrimitive::Layer =>
+                    batch.instances.end = end,
+                _ => self.batches.push(SaikoBatch { primitive, target, instances: start as u32..end }),
+            }
+        }
+        
+        self.is_prepared = true;
     }
 }
 
+//==============================================================================
+//             SaikoBatch
+//==============================================================================
+
+/// The different kinds of primitives that Saiko can draw. Each one has its own pipeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaikoPrimitive {
+    Rect,
+    Glyph,
+    Image,
+    /// Draws the texture of a layer into the target it was pushed in.
+    Layer,
+    Path,
+    Ellipse,
+}
+
+/// A run of primitives of the same kind that are drawn in one instanced draw call.
+#[derive(Debug, Clone)]
+pub struct SaikoBatch {
+    pub primitive: SaikoPrimitive,
+    /// The texture the batch is drawn into. 0 is the ui's render texture, any other target is a layer.
+    pub target: u32,
+    pub instances: Range<u32>,
+}
+
+/// Records the order primitives were pushed in, pointing into the buffer that holds them.
+enum SaikoDrawCommand {
+    Rect(usize),
+    Text(usize),
+    Image(usize),
+    Layer(usize),
+    Path(usize),
+    Icon(usize),
+    Ellipse(usize),
+}
+
 //==============================================================================
 //             SaikoPreparedBuffer
 //==============================================================================
 
 #[derive(Component)]
-pub struct SaikoPreparedBuffer(pub PreparedBindGroup<()>);
+pub struct SaikoPreparedBuffer {
+    pub bind_group: PreparedBindGroup<()>,
+    pub batches: Vec<SaikoBatch>,
+    /// The bind groups that sample each layer's texture, in the same order as [`SaikoBuffer::layers`].
+    pub layer_bind_groups: Vec<BindGroup>,
+}
 
 //==============================================================================
 //             RectBuffer
 //==============================================================================
 
-#[derive(ShaderType, Default)]
+#[derive(ShaderType, Default, Clone, Copy)]
 pub struct RectBuffer {
     pub bound : Bounds,
     pub border_style: BorderStyleBuffer,
     pub fill_style: FillStyleBuffer,
+    pub shadow_style: ShadowStyleBuffer,
+    pub inner_shadow_style: ShadowStyleBuffer,
+    pub nine_slice: NineSliceBuffer,
+    pub clip: u32,
 }
 
 impl RectBuffer {
@@ -75,47 +388,305 @@ impl RectBuffer {
     }
     
     pub fn with_border_width(mut self, width : f32) -> Self {
-        self.border_style.border_width = width;
+        self.border_style.border_width = Vec4::splat(width);
         self
     }
 }
 
 //==============================================================================
-//             ImageBuffer
+//             TextBuffer
+//==============================================================================
+
+/// A line of text that has been pushed by a component, before it is broken into glyphs.
+pub struct TextBuffer {
+    pub bound : Bounds,
+    pub text : String,
+    pub font : AssetId<SaikoFontSdf>,
+    pub font_size : f32,
+    pub color : Color,
+    pub clip : u32,
+}
+
+//==============================================================================
+//             GlyphBuffer
 //==============================================================================
 
-#[derive(ShaderType)]
+/// A single glyph quad that samples the font atlas.
+#[derive(ShaderType, Default, Clone, Copy)]
 pub struct GlyphBuffer {
-    bound : Bounds,
+    pub bound : Bounds,
+    pub color : Color,
+    pub uv_min : Vec2,
+    pub uv_size : Vec2,
+    pub layer : u32,
+    /// The distance range of the msdf, in screen pixels.
+    pub px_range : f32,
+    pub clip : u32,
 }
 
 //==============================================================================
-//             FontIntoBuffer
+//             ImageBuffer
 //==============================================================================
 
-// #[derive(ShaderType)]
-// pub struct FontAtlasBuffer {
-//     fonts : TextureView
-// }
+/// An image that has been pushed by a component, before it has been placed in the image atlas.
+pub struct ImageBuffer {
+    pub bound : Bounds,
+    pub image : AssetId<Image>,
+    /// The part of the image to draw, from (0, 0) at the top left to (1, 1) at the bottom right.
+    pub uv_rect : Rect,
+    pub tint : Color,
+    pub fit : ImageFit,
+    pub border_radius : Vec4,
+    pub clip : u32,
+}
 
-// #[derive(ShaderType)]
-// pub struct FontAtlasFamilyBuffer {
-//     glyphs : Vec<Vec2>,
-//     uv_delta : f32,
-//     layer : u32,
-// }
+/// How an image is scaled to fit its bounds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+pub enum ImageFit {
+    /// The image is stretched to fill the bounds.
+    #[default]
+    Stretch,
+    /// The image is scaled to fit inside the bounds, keeping its aspect ratio.
+    Contain,
+    /// The image is scaled to cover the bounds, keeping its aspect ratio. The parts of the image
+    /// outside of the bounds are cropped.
+    Cover,
+}
 
-// #[derive(ShaderType)]
-// pub struct FontAtlasFamilyBuffer {
-//     glyphs : Vec<>
-// }
+//==============================================================================
+//             IconBuffer
+//==============================================================================
 
-#[derive(ShaderType)]
-pub struct FontAtlasGlyphBuffer {
+/// An svg icon that has been pushed by a component, before it has been turned into paths.
+pub struct IconBuffer {
     pub bound : Bounds,
-    pub family : u32,
+    pub icon : AssetId<SaikoSvg>,
+    /// The color of the parts of the icon that use `currentColor`. Any other color is only faded
+    /// by the alpha of the tint.
+    pub tint : Color,
+    pub clip : u32,
+}
+
+//==============================================================================
+//             ImageQuadBuffer
+//==============================================================================
+
+/// A single image quad that samples the image atlas.
+#[derive(ShaderType, Default, Clone, Copy)]
+pub struct ImageQuadBuffer {
+    pub bound : Bounds,
+    pub tint : Color,
+    pub uv_min : Vec2,
+    pub uv_max : Vec2,
+    pub layer : u32,
+    pub border_radius : Vec4,
+    pub clip : u32,
+}
+
+//==============================================================================
+//             NineSlice
+//==============================================================================
+
+/// A nine-slice image that has been pushed by a component, before it has been placed in the image
+/// atlas. The image is cut into a 3x3 grid by the slice insets. The corners keep their size, the
+/// edges are stretched or tiled along one axis and the center along both.
+#[derive(Clone, Copy)]
+pub struct NineSlice {
+    pub image : AssetId<Image>,
+    /// The insets of the slices in the image, ordered top, right, bottom, left. Percentages are of
+    /// the image height for top and bottom, and of the image width for left and right.
+    pub slice : [Value; 4],
+    pub edge_mode : NineSliceMode,
+    pub center_mode : NineSliceMode,
+    /// How many pixels on screen one pixel of the image takes up.
+    pub scale : f32,
+}
+
+impl Default for NineSlice {
+    fn default() -> Self {
+        NineSlice {
+            image : AssetId::default(),
+            slice : [Value::default(); 4],
+            edge_mode : NineSliceMode::default(),
+            center_mode : NineSliceMode::default(),
+            scale : 1.0,
+        }
+    }
+}
+
+/// How the edges and center of a nine-slice image fill their space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+pub enum NineSliceMode {
+    #[default]
+    Stretch = 0,
+    Tile = 1,
+}
+
+//==============================================================================
+//             NineSliceBuffer
+//==============================================================================
+
+/// A nine-slice image once it is in the image atlas. The slice insets are in image pixels.
+#[derive(ShaderType, Default, Clone, Copy)]
+pub struct NineSliceBuffer {
     pub uv_min : Vec2,
-    pub uv_dim : f32,
+    pub uv_max : Vec2,
+    pub layer : u32,
+    pub image_size : Vec2,
+    pub slice : Vec4,
+    pub scale : f32,
+    pub edge_mode : u32,
+    pub center_mode : u32,
+}
+
+//==============================================================================
+//             ClipBuffer
+//==============================================================================
+
+/// A rounded box that primitives are masked by.
+#[derive(ShaderType, Default, Clone, Copy)]
+pub struct ClipBuffer {
+    pub bound : Bounds,
+    pub border_radius : Vec4,
+    /// The index of the clip this clip was pushed inside of, or 0 if there is none.
+    pub parent : u32,
+}
+
+//==============================================================================
+//             LayerBuffer
+//==============================================================================
+
+/// A layer that is drawn back into its target with a single opacity.
+#[derive(ShaderType, Default, Clone, Copy)]
+pub struct LayerBuffer {
+    pub bound : Bounds,
+    pub opacity : f32,
+    pub clip : u32,
+}
+
+//==============================================================================
+//             EllipseBuffer
+//==============================================================================
+
+/// An ellipse that fills its bounds. It can be hollowed out into a ring, and cut down to an arc.
+#[derive(ShaderType, Clone, Copy)]
+pub struct EllipseBuffer {
+    pub bound : Bounds,
+    pub fill_color : Color,
+    pub stroke_color : Color,
+    pub stroke_width : f32,
+    /// The radius of the hole in the middle, in pixels. A ring keeps an even thickness, so for
+    /// ellipses this is how far the hole is from the shorter radius.
+    pub inner_radius : f32,
+    /// The angles the arc goes between, in radians. An angle of 0 points right, and the arc
+    /// goes counter clockwise from the start angle to the end angle.
+    pub start_angle : f32,
+    pub end_angle : f32,
+    pub clip : u32,
+}
+
+impl Default for EllipseBuffer {
+    fn default() -> Self {
+        EllipseBuffer {
+            bound : Bounds::default(),
+            fill_color : Color::WHITE,
+            stroke_color : Color::NONE,
+            stroke_width : 0.0,
+            inner_radius : 0.0,
+            start_angle : 0.0,
+            end_angle : std::f32::consts::TAU,
+            clip : 0,
+        }
+    }
+}
+
+//==============================================================================
+//             PathBuffer
+//==============================================================================
+
+/// A path, drawn as a single quad that covers all of its segments.
+#[derive(ShaderType, Default, Clone, Copy)]
+pub struct PathBuffer {
+    pub bound : Bounds,
+    pub fill_color : Color,
+    pub stroke_color : Color,
+    pub stroke_width : f32,
+    pub fill_rule : u32,
+    pub segment_start : u32,
+    pub segment_count : u32,
+    pub clip : u32,
+}
+
+/// One curve of a path, or the join between two curves.
+#[derive(ShaderType, Default, Clone, Copy)]
+pub struct PathSegmentBuffer {
+    pub start : Vec2,
+    pub control : Vec2,
+    pub end : Vec2,
+    /// Only used by joins, which are four sided.
+    pub extra : Vec2,
+    pub kind : u32,
+    pub start_cap : u32,
+    pub end_cap : u32,
+}
+
+/// What a [`PathSegmentBuffer`] is used for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathSegmentKind {
+    /// A curve that is both filled and stroked.
+    Curve = 0,
+    /// The line that closes an open subpath. It is filled, but not stroked.
+    Close = 1,
+    /// A miter or bevel join, made of the four points of the segment.
+    Join = 2,
+    /// A round join, centered on the start of the segment.
+    RoundJoin = 3,
+}
+
+/// The shape of the ends of an open path's stroke.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+pub enum LineCap {
+    /// The stroke stops at the end of the path.
+    #[default]
+    Butt = 0,
+    /// The stroke reaches half its width past the end of the path.
+    Square = 1,
+    Round = 2,
+}
+
+/// The shape of the outside of the corner where two curves of a stroke meet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+pub enum LineJoin {
+    #[default]
+    Miter,
+    Bevel,
+    Round,
+}
+
+/// How overlapping parts of a path decide whether they are inside the fill.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+pub enum FillRule {
+    /// Inside is anywhere the path winds around a non zero number of times.
+    #[default]
+    NonZero = 0,
+    /// Inside is anywhere the path crosses an odd number of times, so overlaps make holes.
+    EvenOdd = 1,
+}
+
+//==============================================================================
+//             BackdropBuffer
+//==============================================================================
+
+/// A rounded box that shows a blurred copy of the scene behind it.
+#[derive(ShaderType, Default, Clone, Copy)]
+pub struct BackdropBuffer {
+    pub bound : Bounds,
+    pub border_radius : Vec4,
+    /// Roughly how many pixels the scene is blurred by.
+    pub blur_radius : f32,
+    /// The opacity of every layer the backdrop is inside of, multiplied together.
+    pub opacity : f32,
+    pub clip : u32,
 }
 
 //==============================================================================
@@ -125,8 +696,14 @@ pub struct FontAtlasGlyphBuffer {
 #[derive(ShaderType, Clone, Copy)]
 pub struct BorderStyleBuffer {
     pub border_color: Color,
+    /// The radius of each corner, in the order top left, bottom left, top right, bottom right.
     pub border_radius: Vec4,
-    pub border_width: f32,
+    /// The width of each side, in the order top, right, bottom, left.
+    pub border_width: Vec4,
+    pub border_alignment: u32,
+    pub border_pattern: u32,
+    pub dash_length: f32,
+    pub dash_gap: f32,
 }
 
 impl Default for BorderStyleBuffer {
@@ -134,7 +711,67 @@ impl Default for BorderStyleBuffer {
         BorderStyleBuffer {
             border_color: Color::BLACK,
             border_radius: Vec4::ZERO,
-            border_width: 5.0,
+            border_width: Vec4::splat(5.0),
+            border_alignment: BorderAlignment::Center as u32,
+            border_pattern: BorderPattern::Solid as u32,
+            dash_length: 0.0,
+            dash_gap: 0.0,
+        }
+    }
+}
+
+/// Where the border is drawn in relation to the edge of the rect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+pub enum BorderAlignment {
+    /// The border is drawn inside the rect.
+    Inside = 0,
+    /// The border is centered on the edge of the rect.
+    #[default]
+    Center = 1,
+    /// The border is drawn outside the rect.
+    Outside = 2,
+}
+
+/// How the border is stroked along the edge of the rect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+pub enum BorderPattern {
+    #[default]
+    Solid = 0,
+    Dashed = 1,
+    Dotted = 2,
+}
+
+//==============================================================================
+//             ShadowStyleBuffer
+//==============================================================================
+
+/// A shadow cast by a rect. A shadow with a transparent color is not drawn.
+#[derive(ShaderType, Clone, Copy)]
+pub struct ShadowStyleBuffer {
+    pub shadow_color: Color,
+    pub shadow_offset: Vec2,
+    pub shadow_blur: f32,
+    pub shadow_spread: f32,
+}
+
+impl Default for ShadowStyleBuffer {
+    fn default() -> Self {
+        ShadowStyleBuffer {
+            shadow_color: Color::NONE,
+            shadow_offset: Vec2::ZERO,
+            shadow_blur: 0.0,
+            shadow_spread: 0.0,
+        }
+    }
+}
+
+impl ShadowStyleBuffer {
+    pub fn new(offset: Vec2, blur: f32, spread: f32, color: Color) -> Self {
+        ShadowStyleBuffer {
+            shadow_color: color,
+            shadow_offset: offset,
+            shadow_blur: blur.max(0.0),
+            shadow_spread: spread,
         }
     }
 }
@@ -143,15 +780,107 @@ impl Default for BorderStyleBuffer {
 //             FillStyleBuffer
 //==============================================================================
 
+/// The most color stops a gradient can have.
+pub const MAX_GRADIENT_STOPS: usize = 4;
+
 #[derive(ShaderType, Clone, Copy)]
 pub struct FillStyleBuffer {
     pub fill_color: Color,
+    pub fill_type: u32,
+    /// The center of a radial or conic gradient, from (0, 0) at the bottom left of the rect
+    /// to (1, 1) at the top right.
+    pub gradient_center: Vec2,
+    /// The direction of a linear gradient, or the start angle of a conic gradient, in radians.
+    pub gradient_angle: f32,
+    pub stop_count: u32,
+    pub stop_offsets: Vec4,
+    pub stop_colors: [Color; MAX_GRADIENT_STOPS],
 }
 
 impl Default for FillStyleBuffer {
     fn default() -> Self {
         FillStyleBuffer {
             fill_color: Color::WHITE,
+            fill_type: FillType::Solid as u32,
+            gradient_center: Vec2::splat(0.5),
+            gradient_angle: 0.0,
+            stop_count: 0,
+            stop_offsets: Vec4::ZERO,
+            stop_colors: [Color::NONE; MAX_GRADIENT_STOPS],
         }
     }
-}
\ No newline at end of file
+}
+
+impl FillStyleBuffer {
+    /// Sets the color stops of the gradient. Each stop is an offset between 0 and 1 and a color.
+    /// Only the first [`MAX_GRADIENT_STOPS`] stops are used.
+    pub fn set_stops(&mut self, stops : impl IntoIterator<Item = (f32, Color)>) {
+        self.stop_count = 0;
+        for (index, (offset, color)) in stops.into_iter().take(MAX_GRADIENT_STOPS).enumerate() {
+            self.stop_offsets[index] = offset.clamp(0.0, 1.0);
+            self.stop_colors[index] = color;
+            self.stop_count += 1;
+        }
+    }
+}
+
+/// How the inside of a rect is filled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+pub enum FillType {
+    #[default]
+    Solid = 0,
+    LinearGradient = 1,
+    RadialGradient = 2,
+    ConicGradient = 3,
+    /// The rect is filled with a nine-slice image. See [`NineSlice`].
+    NineSlice = 4,
+}
+
+//==============================================================================
+//             Tests
+//==============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backdrop() -> BackdropBuffer {
+        BackdropBuffer {
+            bound : Bounds::new(Vec2::ZERO, Vec2::splat(50.0), 0),
+            blur_radius : 8.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn clipped_backdrops_are_clipped() {
+        let mut buffer = SaikoBuffer::new(1.0);
+        buffer.push_backdrop(backdrop());
+        buffer.push_clip(Bounds::new(Vec2::ZERO, Vec2::splat(20.0), 0), Vec4::ZERO);
+        buffer.push_clip(Bounds::new(Vec2::splat(10.0), Vec2::splat(20.0), 0), Vec4::ZERO);
+        buffer.push_backdrop(backdrop());
+        buffer.pop_clip();
+        buffer.pop_clip();
+        buffer.push_backdrop(backdrop());
+
+        let clips = buffer.backdrops.iter().map(|backdrop| backdrop.clip).collect::<Vec<_>>();
+        assert_eq!(clips, vec![0, 2, 0]);
+        assert_eq!(buffer.clips[2].bound.center, Vec2::splat(10.0));
+        assert_eq!(buffer.clips[2].parent, 1);
+    }
+
+    #[test]
+    fn backdrops_take_the_opacity_of_their_layers() {
+        let mut buffer = SaikoBuffer::new(1.0);
+        buffer.push_layer(Bounds::default(), 0.5);
+        buffer.push_layer(Bounds::default(), 0.5);
+        buffer.push_backdrop(backdrop());
+        buffer.pop_layer();
+        buffer.push_backdrop(backdrop());
+        buffer.pop_layer();
+        buffer.push_backdrop(backdrop());
+
+        let opacities = buffer.backdrops.iter().map(|backdrop| backdrop.opacity).collect::<Vec<_>>();
+        assert_eq!(opacities, vec![0.25, 0.5, 1.0]);
+    }
+}
@@ -61,14 +61,7 @@ impl OutlineBuilder for GlyphBuilder {
     }
 
     fn line_to(&mut self, x: f32, y: f32) {
-        let p0 = self.current_pos;
-        let p2 = Vec2::new(x, y);
-        let p1 = p2 * 0.5 - p0 * 0.5 + p0;
-        self.lines.push(GlyphCurve{
-            start : p0, 
-            control : p1, 
-            end : p2
-        });
+        self.lines.push(GlyphCurve::line(self.current_pos, Vec2::new(x, y)));
         self.move_to(x, y);
         
         // println!("M {} {} Q {} {} {x} {y} Z", p0.x, p0.y, p1.x, p1.y)
@@ -86,19 +79,12 @@ impl OutlineBuilder for GlyphBuilder {
     }
 
     fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
-        let q0 = self.current_pos;
-        let q1 = Vec2::new(x1, y1);
-        let q2 = Vec2::new(x2, y2);
-        let q3 = Vec2::new(x, y);
-        let quarter = 1.0 / 4.0;
-        let three_quarters = 3.0 / 4.0;
-        let p1 = -quarter * q0 + three_quarters * q1 + three_quarters * q2 - quarter * q3;
-        
-        self.lines.push(GlyphCurve {
-            start : q0, 
-            control : p1, 
-            end : q3
-        });
+        self.lines.push(GlyphCurve::from_cubic(
+            self.current_pos,
+            Vec2::new(x1, y1),
+            Vec2::new(x2, y2),
+            Vec2::new(x, y),
+        ));
         self.move_to(x, y)
     }
 
@@ -114,11 +100,36 @@ impl OutlineBuilder for GlyphBuilder {
 //     Curve(Vec2, Vec2, Vec2),
 // }
 
+/// A quadratic bezier curve. Lines and cubic curves are stored as quadratic curves too, so
+/// everything that draws outlines only has to handle one kind of curve.
 #[derive(Debug, Clone, Copy)]
-struct GlyphCurve {
-    start : Vec2,
-    control : Vec2,
-    end : Vec2
+pub(crate) struct GlyphCurve {
+    pub(crate) start : Vec2,
+    pub(crate) control : Vec2,
+    pub(crate) end : Vec2
+}
+
+impl GlyphCurve {
+    pub(crate) fn line(start : Vec2, end : Vec2) -> Self {
+        GlyphCurve {
+            start,
+            control : start.lerp(end, 0.5),
+            end
+        }
+    }
+    
+    /// Approximates a cubic curve with a single quadratic curve. This is only close for short,
+    /// gentle curves, so longer curves should be split up first.
+    pub(crate) fn from_cubic(q0 : Vec2, q1 : Vec2, q2 : Vec2, q3 : Vec2) -> Self {
+        let quarter = 1.0 / 4.0;
+        let three_quarters = 3.0 / 4.0;
+        
+        GlyphCurve {
+            start : q0,
+            control : -quarter * q0 + three_quarters * q1 + three_quarters * q2 - quarter * q3,
+            end : q3
+        }
+    }
 }
 
 // impl GlyphOutline {
@@ -1,11 +1,13 @@
 
 
-use bevy::{asset::{AssetLoader, AsyncReadExt}, ecs::system::CommandQueue, math::U16Vec2, prelude::*, render::{extract_resource::ExtractResource, render_resource::{Extent3d, ImageCopyTexture, ImageDataLayout, Origin3d, Texture, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages}, renderer::{RenderDevice, RenderQueue}, texture::GpuImage, Extract, RenderApp}, utils::{BoxedFuture, HashMap}};
+use bevy::{asset::{AssetLoader, AsyncReadExt}, ecs::system::CommandQueue, math::U16Vec2, prelude::*, render::{extract_resource::ExtractResource, render_resource::{binding_types::texture_2d_array, BindGroup, BindGroupEntries, BindGroupLayout, BindGroupLayoutEntries, Extent3d, ImageCopyTexture, ImageDataLayout, Origin3d, ShaderStages, Texture, TextureDescriptor, TextureDimension, TextureFormat, TextureSampleType, TextureUsages, TextureViewDescriptor, TextureViewDimension}, renderer::{RenderDevice, RenderQueue}, texture::GpuImage, Extract, RenderApp}, utils::{BoxedFuture, HashMap, HashSet}};
 use etagere::{euclid::{Box2D, UnknownUnit}, Allocation, AtlasAllocator, Size};
 use thiserror::Error;
 use ttf_parser::{Face, GlyphId};
 use msdfgen::{Bitmap, FontExt, MsdfGeneratorConfig, Range, Rgb, Rgba};
 
+use crate::{common::{bounds::Bounds, MarkSaikoUiDirty}, render::buffer::{GlyphBuffer, TextBuffer}};
+
 use super::SaikoCharacterSet;
 
 
@@ -16,6 +18,7 @@ impl Plugin for SaikoFontSdfPlugin {
         app
             .init_asset_loader::<SaikoFontSdfLoader>()
             .init_asset::<SaikoFontSdf>()
+            .add_systems(Update, mark_ui_dirty_on_font_load)
         ;
         
         let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
@@ -40,22 +43,36 @@ impl Plugin for SaikoFontSdfPlugin {
 //             SaikoFont Systems
 //==============================================================================
 
-/// This system extracts fonts from the world and puts them into the correct font atlases
+/// This system extracts fonts from the world and puts them into the correct font atlases. Fonts
+/// that were modified are written over the layer they already have.
 fn extract_sdf_fonts (
     mut gpu_font_atlas : ResMut<SaikoGPUFontAtlas>,
+    mut font_events : Extract<EventReader<AssetEvent<SaikoFontSdf>>>,
     fonts : Extract<Res<Assets<SaikoFontSdf>>>,
     render_queue : ResMut<RenderQueue>
 ) {
+    let modified = font_events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect::<HashSet<_>>();
+    
     for (id, font) in fonts.iter() {
-        if gpu_font_atlas.layers.contains_key(&id) || !font.is_dirty {
-            println!("Font is not dirty");
-            continue;
-        }
-        
-        let layer = if let Some(layer) = gpu_font_atlas.layers.get(&id) { *layer } else {
-            let layer = gpu_font_atlas.next_layer;
-            gpu_font_atlas.next_layer += 1;
-            layer
+        let layer = match gpu_font_atlas.fonts.get(&id) {
+            Some(gpu_font) if modified.contains(&id) => gpu_font.layer,
+            Some(_) => continue,
+            None if !font.is_dirty => continue,
+            None => {
+                if gpu_font_atlas.next_layer >= FONT_ATLAS_LAYERS {
+                    warn!("The Saiko font atlas is full, font {:?} will not be rendered.", id);
+                    continue;
+                }
+                
+                gpu_font_atlas.next_layer += 1;
+                gpu_font_atlas.next_layer - 1
+            },
         };
         
         render_queue.write_texture(
@@ -74,7 +91,28 @@ fn extract_sdf_fonts (
             Extent3d { width: FONT_ATLAS_DIMS, height: FONT_ATLAS_DIMS, depth_or_array_layers: 1 }
         );
         
-        gpu_font_atlas.layers.insert(id, layer);
+        gpu_font_atlas.fonts.insert(id, SaikoGPUFont {
+            layer,
+            glyphs : font.glyph_data.clone(),
+            advances : font.advances.clone(),
+            metrics : font.metrics,
+            glyph_size : font.glyph_size,
+        });
+    }
+}
+
+/// Text that was pushed before its font finished loading has no glyphs, so the ui is redrawn
+/// once the font is ready.
+fn mark_ui_dirty_on_font_load(
+    mut font_events : EventReader<AssetEvent<SaikoFontSdf>>,
+    mut dirty : EventWriter<MarkSaikoUiDirty>,
+) {
+    let font_loaded = font_events
+        .read()
+        .any(|event| matches!(event, AssetEvent::LoadedWithDependencies { .. } | AssetEvent::Modified { .. }));
+    
+    if font_loaded {
+        dirty.send(MarkSaikoUiDirty);
     }
 }
 
@@ -90,12 +128,18 @@ fn initialize_sdf_font_texture (
 //==============================================================================
 
 const FONT_ATLAS_DIMS : u32 = 2048;
+const FONT_ATLAS_LAYERS : u32 = 16;
+
+/// The width of the distance range encoded around each glyph, in atlas pixels.
+const SDF_PIXEL_RANGE : f64 = 4.0;
 
 #[derive(Asset, TypePath, Clone)]
 pub struct SaikoFontSdf {
     bitmap : Vec<Rgba<f32>>,
     allocator : AtlasAllocator,
     glyph_data : HashMap<char, SaikoGlyphData>,
+    advances : HashMap<char, f32>,
+    metrics : SaikoFontMetrics,
     glyph_size : u32,
     is_dirty : bool,
 }
@@ -106,7 +150,15 @@ impl Default for SaikoFontSdf {
         let allocator = AtlasAllocator::new(Size::splat(FONT_ATLAS_DIMS as i32));
         let glyph_size = 32;
         
-        Self { bitmap, allocator, glyph_size, glyph_data : HashMap::default(), is_dirty : true }
+        Self { 
+            bitmap, 
+            allocator, 
+            glyph_size, 
+            glyph_data : HashMap::default(), 
+            advances : HashMap::default(), 
+            metrics : SaikoFontMetrics::default(), 
+            is_dirty : true 
+        }
     }
 }
 
@@ -119,13 +171,26 @@ impl SaikoFontSdf {
     
     pub fn add_glyph(&mut self, font : &Face, character : char) -> bool {
         let Some(glyph) = font.glyph_index(character) else { return false };
+        
+        // Glyphs without an outline (like spaces) still need to move the pen.
+        let advance = font.glyph_hor_advance(glyph).unwrap_or(0) as f32;
+        self.advances.insert(character, advance);
+        
         let Some(mut glyph_shape) = font.glyph_shape(glyph) else { return false };
-        let Some(allocation) = self.allocate_glyph(character, glyph, &font) else { return false };
         
         let bound = glyph_shape.get_bound();
-        let Some(framing) = bound.autoframe(self.glyph_size, self.glyph_size, Range::Px(4.0), None)
+        let Some(framing) = bound.autoframe(self.glyph_size, self.glyph_size, Range::Px(SDF_PIXEL_RANGE), None)
             else { return false };
         
+        // The framing maps font units to atlas pixels with `(p + translate) * scale`. Inverting it
+        // gives the area of the glyph's em box that the atlas cell covers.
+        let scale = Vec2::new(framing.projection.scale.x as f32, framing.projection.scale.y as f32);
+        let translate = Vec2::new(framing.projection.translate.x as f32, framing.projection.translate.y as f32);
+        let plane_min = -translate;
+        let plane_size = Vec2::splat(self.glyph_size as f32) / scale;
+        
+        let Some(allocation) = self.allocate_glyph(character, glyph, &font, plane_min, plane_size) else { return false };
+        
         let mut bitmap = Bitmap::new(self.glyph_size, self.glyph_size);
         
         glyph_shape.edge_coloring_simple(3.0, 0);
@@ -177,11 +242,15 @@ impl SaikoFontSdf {
         }
     }
     
-    fn allocate_glyph(&mut self, character : char, glyph_id : GlyphId, font : &Face) -> Option<Allocation> {
-        let Some(allocation) = self.allocator.allocate(Size::splat(32)) else { return None };
+    fn allocate_glyph(&mut self, character : char, glyph_id : GlyphId, font : &Face, plane_min : Vec2, plane_size : Vec2) -> Option<Allocation> {
+        let Some(allocation) = self.allocator.allocate(Size::splat(self.glyph_size as i32)) else { return None };
         let rect = self.allocator.get(allocation.id);
         
-        let data = SaikoGlyphData::from_font(glyph_id, font, allocation, rect);
+        let mut data = SaikoGlyphData::from_font(glyph_id, font, allocation, rect);
+        data.plane_min = plane_min;
+        data.plane_size = plane_size;
+        data.uv_size = Vec2::splat(self.glyph_size as f32 / FONT_ATLAS_DIMS as f32);
+        
         self.glyph_data.insert(character, data);
         Some(allocation)
     }
@@ -189,6 +258,48 @@ impl SaikoFontSdf {
     pub fn glyph_data(&self, character : char) -> Option<&SaikoGlyphData> {
         self.glyph_data.get(&character)
     }
+    
+    /// The horizontal advance of a character in font units.
+    pub fn advance(&self, character : char) -> Option<f32> {
+        self.advances.get(&character).copied()
+    }
+    
+    pub fn metrics(&self) -> &SaikoFontMetrics {
+        &self.metrics
+    }
+    
+    pub fn set_metrics(&mut self, font : &Face) {
+        self.metrics = SaikoFontMetrics::from_face(font);
+    }
+    
+    /// The size in pixels of a single glyph in the atlas.
+    pub fn glyph_size(&self) -> u32 {
+        self.glyph_size
+    }
+}
+
+//==============================================================================
+//             SaikoFontMetrics
+//==============================================================================
+
+/// The vertical metrics of a font, in font units.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SaikoFontMetrics {
+    pub units_per_em : f32,
+    pub ascender : f32,
+    pub descender : f32,
+    pub line_gap : f32,
+}
+
+impl SaikoFontMetrics {
+    fn from_face(font : &Face) -> Self {
+        Self {
+            units_per_em : font.units_per_em() as f32,
+            ascender : font.ascender() as f32,
+            descender : font.descender() as f32,
+            line_gap : font.line_gap() as f32,
+        }
+    }
 }
 
 //==============================================================================
@@ -201,6 +312,9 @@ pub struct SaikoGlyphData {
     advance : U16Vec2,
     vertical_origin : i16,
     min : Vec2,
+    uv_size : Vec2,
+    plane_min : Vec2,
+    plane_size : Vec2,
 }
 
 impl SaikoGlyphData {
@@ -217,7 +331,7 @@ impl SaikoGlyphData {
         let min = Vec2::new(rect.min.x as f32 / font_atlas_dims_float, rect.min.y as f32 / font_atlas_dims_float);
         // let max = Vec2::new(rect.max.x as f32 / font_atlas_dims_float, rect.max.y as f32 / font_atlas_dims_float);
         
-        Self { advance, allocation, vertical_origin, min }
+        Self { advance, allocation, vertical_origin, min, uv_size : Vec2::ZERO, plane_min : Vec2::ZERO, plane_size : Vec2::ZERO }
     }
     
     pub fn advance(&self) -> U16Vec2 {
@@ -235,6 +349,21 @@ impl SaikoGlyphData {
     pub fn min(&self) -> Vec2 {
         self.min
     }
+    
+    /// The size of the glyph's cell in the atlas, in uv space.
+    pub fn uv_size(&self) -> Vec2 {
+        self.uv_size
+    }
+    
+    /// The bottom left corner of the area the atlas cell covers, in font units relative to the pen.
+    pub fn plane_min(&self) -> Vec2 {
+        self.plane_min
+    }
+    
+    /// The size of the area the atlas cell covers, in font units.
+    pub fn plane_size(&self) -> Vec2 {
+        self.plane_size
+    }
 }
 
 //==============================================================================
@@ -265,6 +394,7 @@ impl AssetLoader for SaikoFontSdfLoader {
             let Ok(font) = Face::parse(&data, 0) else { return Err(SaikoSdfFontError::Io) };
             let mut font_atlas = SaikoFontSdf::default();
             
+            font_atlas.set_metrics(&font);
             font_atlas.add_glyphs(&font, SaikoCharacterSet::ascii());
             
             Ok(font_atlas)
@@ -292,13 +422,72 @@ pub enum SaikoSdfFontError {
 //             SaikoGPUFontAtlas
 //==============================================================================
 
+/// The render world copy of every loaded font. Each font gets its own layer in the atlas texture,
+/// and keeps the glyph metrics needed to lay out text.
 #[derive(Resource)]
-struct SaikoGPUFontAtlas {
+pub(crate) struct SaikoGPUFontAtlas {
     texture : Texture,
-    layers : HashMap<AssetId<SaikoFontSdf>, u32>,
+    bind_group : BindGroup,
+    fonts : HashMap<AssetId<SaikoFontSdf>, SaikoGPUFont>,
     next_layer : u32,
 }
 
+impl SaikoGPUFontAtlas {
+    pub(crate) fn bind_group_layout(render_device : &RenderDevice) -> BindGroupLayout {
+        render_device.create_bind_group_layout(
+            "saiko_font_atlas_bind_group_layout",
+            &BindGroupLayoutEntries::single(
+                ShaderStages::FRAGMENT,
+                texture_2d_array(TextureSampleType::Float { filterable: false }),
+            ),
+        )
+    }
+    
+    pub(crate) fn bind_group(&self) -> &BindGroup {
+        &self.bind_group
+    }
+    
+    /// Lays out a line of text on to the glyph buffer. The text starts at the left edge of its bounds, 
+    /// and the baseline is placed so the text is vertically centered.
+    pub(crate) fn layout_text(&self, text : &TextBuffer, glyphs : &mut Vec<GlyphBuffer>) {
+        let Some(font) = self.fonts.get(&text.font) else { return };
+        if font.metrics.units_per_em <= 0.0 { return }
+        
+        let scale = text.font_size / font.metrics.units_per_em;
+        let line_height = (font.metrics.ascender - font.metrics.descender + font.metrics.line_gap) * scale;
+        let line_start = text.bound.center.x - text.bound.size.x;
+        let mut pen = Vec2::new(
+            line_start,
+            text.bound.center.y - (font.metrics.ascender + font.metrics.descender) * 0.5 * scale
+        );
+        
+        for character in text.text.chars() {
+            if character == '\n' {
+                pen.x = line_start;
+                pen.y -= line_height;
+                continue;
+            }
+            
+            if let Some(glyph) = font.glyphs.get(&character) {
+                let size = glyph.plane_size() * scale;
+                let center = pen + glyph.plane_min() * scale + size * 0.5;
+                
+                glyphs.push(GlyphBuffer {
+                    bound : Bounds::new(center, size * 0.5, text.bound.z_index),
+                    color : text.color,
+                    uv_min : glyph.min(),
+                    uv_size : glyph.uv_size(),
+                    layer : font.layer,
+                    px_range : SDF_PIXEL_RANGE as f32 * size.x / font.glyph_size as f32,
+                    clip : text.clip,
+                });
+            }
+            
+            pen.x += font.advances.get(&character).copied().unwrap_or(0.0) * scale;
+        }
+    }
+}
+
 impl FromWorld for SaikoGPUFontAtlas {
     fn from_world(world: &mut World) -> Self {
         let render_device = world.get_resource::<RenderDevice>().unwrap();
@@ -309,19 +498,38 @@ impl FromWorld for SaikoGPUFontAtlas {
                 size: Extent3d {
                     width: FONT_ATLAS_DIMS,
                     height: FONT_ATLAS_DIMS,
-                    depth_or_array_layers: 16,
+                    depth_or_array_layers: FONT_ATLAS_LAYERS,
                 }, 
                 mip_level_count: 1, 
                 sample_count: 1, 
-                dimension: TextureDimension::D3, 
+                dimension: TextureDimension::D2, 
                 format: TextureFormat::Rgba32Float, 
                 usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
                 view_formats: &[] 
             }
         );
         
+        let font_texture_view = font_texture.create_view(&TextureViewDescriptor {
+            label: Some("SaikoFontSdfAtlas View"),
+            dimension: Some(TextureViewDimension::D2Array),
+            ..Default::default()
+        });
         
+        let bind_group = render_device.create_bind_group(
+            "saiko_font_atlas_bind_group",
+            &Self::bind_group_layout(render_device),
+            &BindGroupEntries::single(&font_texture_view),
+        );
         
-        Self { texture : font_texture, layers : HashMap::new(), next_layer : 0 }
+        Self { texture : font_texture, bind_group, fonts : HashMap::new(), next_layer : 0 }
     }
 }
+
+/// A single font that has been uploaded to the [`SaikoGPUFontAtlas`].
+struct SaikoGPUFont {
+    layer : u32,
+    glyphs : HashMap<char, SaikoGlyphData>,
+    advances : HashMap<char, f32>,
+    metrics : SaikoFontMetrics,
+    glyph_size : u32,
+}
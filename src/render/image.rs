@@ -0,0 +1,380 @@
+//==============================================================================
+//  Images drawn by Saiko are packed into a shared atlas, so that a single
+//  draw call can contain any number of images. Each image is copied into
+//  the atlas the first time it is drawn, and again whenever it changes.
+//==============================================================================
+
+use bevy::{
+    core_pipeline::fullscreen_vertex_shader::fullscreen_shader_vertex_state,
+    prelude::*,
+    render::{
+        render_asset::RenderAssets,
+        render_resource::{
+            binding_types::{sampler, texture_2d, texture_2d_array},
+            BindGroup, BindGroupEntries, BindGroupLayout, BindGroupLayoutEntries,
+            CachedRenderPipelineId, ColorTargetState, ColorWrites, CommandEncoderDescriptor, Extent3d,
+            FilterMode, FragmentState, LoadOp, MultisampleState, Operations, PipelineCache,
+            PrimitiveState, RenderPassColorAttachment, RenderPassDescriptor, RenderPipelineDescriptor,
+            Sampler, SamplerBindingType, SamplerDescriptor, ShaderStages, StoreOp, TextureDescriptor,
+            TextureDimension, TextureFormat, TextureSampleType, TextureUsages, TextureView,
+            TextureViewDescriptor, TextureViewDimension,
+        },
+        renderer::{RenderDevice, RenderQueue},
+        texture::BevyDefault,
+        Extract, RenderApp,
+    },
+    utils::{HashMap, HashSet},
+};
+use etagere::{AllocId, AtlasAllocator, Size};
+
+use crate::common::{bounds::Bounds, MarkSaikoUiDirty};
+
+use super::{buffer::{ImageBuffer, ImageFit, ImageQuadBuffer, NineSlice, NineSliceBuffer}, BLIT_SHADER_HANDLE};
+
+//==============================================================================
+//             SaikoImagePlugin
+//==============================================================================
+
+pub struct SaikoImagePlugin;
+
+impl Plugin for SaikoImagePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, mark_ui_dirty_on_image_load);
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app
+            .init_resource::<SaikoGPUImageAtlas>()
+            .add_systems(ExtractSchedule, extract_image_events)
+        ;
+    }
+}
+
+/// Images that changed are taken out of the atlas, so the next time they are drawn they are
+/// uploaded again with their new pixels.
+fn extract_image_events(
+    mut image_atlas : ResMut<SaikoGPUImageAtlas>,
+    mut image_events : Extract<EventReader<AssetEvent<Image>>>,
+) {
+    for event in image_events.read() {
+        match event {
+            AssetEvent::Modified { id } | AssetEvent::Removed { id } => image_atlas.remove(*id),
+            _ => {},
+        }
+    }
+}
+
+/// Images that are drawn before they finish loading are skipped, so the ui is redrawn once they are ready.
+fn mark_ui_dirty_on_image_load(
+    mut image_events : EventReader<AssetEvent<Image>>,
+    mut dirty : EventWriter<MarkSaikoUiDirty>,
+) {
+    let image_loaded = image_events
+        .read()
+        .any(|event| matches!(event, AssetEvent::LoadedWithDependencies { .. } | AssetEvent::Modified { .. }));
+
+    if image_loaded {
+        dirty.send(MarkSaikoUiDirty);
+    }
+}
+
+//==============================================================================
+//             SaikoGPUImageAtlas
+//==============================================================================
+
+const IMAGE_ATLAS_DIMS : u32 = 2048;
+const IMAGE_ATLAS_LAYERS : u32 = 8;
+
+/// The space left around each image in the atlas, so filtering does not bleed between images.
+const IMAGE_ATLAS_PADDING : i32 = 1;
+
+/// Every image that Saiko has drawn, packed into the layers of a texture array.
+#[derive(Resource)]
+pub(crate) struct SaikoGPUImageAtlas {
+    layer_views : Vec<TextureView>,
+    allocators : Vec<AtlasAllocator>,
+    images : HashMap<AssetId<Image>, SaikoAtlasImage>,
+    /// Images that don't fit in the atlas, so they are only warned about once.
+    oversized : HashSet<AssetId<Image>>,
+    bind_group : BindGroup,
+    upload_pipeline : CachedRenderPipelineId,
+    upload_bind_group_layout : BindGroupLayout,
+    upload_sampler : Sampler,
+}
+
+/// Where an image has been placed in the atlas.
+#[derive(Clone, Copy)]
+struct SaikoAtlasImage {
+    allocation : AllocId,
+    layer : u32,
+    min : Vec2,
+    size : Vec2,
+}
+
+impl SaikoGPUImageAtlas {
+    pub(crate) fn bind_group_layout(render_device : &RenderDevice) -> BindGroupLayout {
+        render_device.create_bind_group_layout(
+            "saiko_image_atlas_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::FRAGMENT,
+                (
+                    texture_2d_array(TextureSampleType::Float { filterable: true }),
+                    sampler(SamplerBindingType::Filtering),
+                ),
+            ),
+        )
+    }
+
+    pub(crate) fn bind_group(&self) -> &BindGroup {
+        &self.bind_group
+    }
+
+    /// Copies every image that is not in the atlas yet into it. Images that are still loading are
+    /// skipped, and will be uploaded the next time the ui is drawn.
+    pub(crate) fn upload<'a>(
+        &mut self,
+        images : impl IntoIterator<Item = &'a AssetId<Image>>,
+        gpu_images : &RenderAssets<Image>,
+        pipeline_cache : &PipelineCache,
+        render_device : &RenderDevice,
+        render_queue : &RenderQueue,
+    ) {
+        let pending = images
+            .into_iter()
+            .filter(|id| !self.images.contains_key(*id) && !self.oversized.contains(*id))
+            .collect::<Vec<_>>();
+        if pending.is_empty() { return }
+
+        let Some(upload_pipeline) = pipeline_cache.get_render_pipeline(self.upload_pipeline) else { return };
+
+        let mut encoder = render_device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("SaikoUI Image Atlas Upload"),
+        });
+
+        for id in pending {
+            if self.images.contains_key(id) { continue }
+            let Some(gpu_image) = gpu_images.get(*id) else { continue };
+            let Some(atlas_image) = self.allocate(*id, gpu_image.size) else { continue };
+
+            let bind_group = render_device.create_bind_group(
+                "saiko_image_upload_bind_group",
+                &self.upload_bind_group_layout,
+                &BindGroupEntries::sequential((&gpu_image.texture_view, &self.upload_sampler)),
+            );
+
+            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("SaikoUI Image Atlas Upload Pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: &self.layer_views[atlas_image.layer as usize],
+                    resolve_target: None,
+                    ops: Operations { load: LoadOp::Load, store: StoreOp::Store },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            // The fullscreen triangle only covers the viewport, which is the image's spot in the atlas.
+            render_pass.set_pipeline(upload_pipeline);
+            render_pass.set_bind_group(0, &bind_group, &[]);
+            render_pass.set_viewport(atlas_image.min.x, atlas_image.min.y, atlas_image.size.x, atlas_image.size.y, 0.0, 1.0);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        render_queue.submit([encoder.finish()]);
+    }
+
+    fn allocate(&mut self, id : AssetId<Image>, size : Vec2) -> Option<SaikoAtlasImage> {
+        let padded_size = Size::new(size.x as i32 + IMAGE_ATLAS_PADDING * 2, size.y as i32 + IMAGE_ATLAS_PADDING * 2);
+
+        for (layer, allocator) in self.allocators.iter_mut().enumerate() {
+            let Some(allocation) = allocator.allocate(padded_size) else { continue };
+
+            let atlas_image = SaikoAtlasImage {
+                allocation : allocation.id,
+                layer : layer as u32,
+                min : Vec2::new(
+                    (allocation.rectangle.min.x + IMAGE_ATLAS_PADDING) as f32,
+                    (allocation.rectangle.min.y + IMAGE_ATLAS_PADDING) as f32,
+                ),
+                size,
+            };
+            self.images.insert(id, atlas_image);
+            return Some(atlas_image);
+        }
+
+        warn!("Image {:?} does not fit in the Saiko image atlas, and will not be drawn.", id);
+        self.oversized.insert(id);
+        None
+    }
+
+    /// Frees the space of the image in the atlas.
+    fn remove(&mut self, id : AssetId<Image>) {
+        self.oversized.remove(&id);
+        let Some(atlas_image) = self.images.remove(&id) else { return };
+        self.allocators[atlas_image.layer as usize].deallocate(atlas_image.allocation);
+    }
+
+    /// Fits the image inside of its bounds, and pushes the quad that draws it.
+    pub(crate) fn layout_image(&self, image : &ImageBuffer, quads : &mut Vec<ImageQuadBuffer>) {
+        let Some(atlas_image) = self.images.get(&image.image) else { return };
+
+        let uv_min = image.uv_rect.min;
+        let uv_size = image.uv_rect.size();
+        let source_size = (atlas_image.size * uv_size).abs().max(Vec2::splat(1.0));
+        let half_size = image.bound.size;
+
+        let (quad_size, uv_min, uv_size) = match image.fit {
+            ImageFit::Stretch => (half_size, uv_min, uv_size),
+            ImageFit::Contain => {
+                let scale = (half_size.x / source_size.x).min(half_size.y / source_size.y);
+                (source_size * scale, uv_min, uv_size)
+            },
+            ImageFit::Cover => {
+                let scale = (half_size.x / source_size.x).max(half_size.y / source_size.y);
+                let visible = (half_size / scale) / source_size;
+                let cropped_size = uv_size * visible;
+                (half_size, uv_min + (uv_size - cropped_size) * 0.5, cropped_size)
+            },
+        };
+
+        let atlas_dims = Vec2::splat(IMAGE_ATLAS_DIMS as f32);
+
+        quads.push(ImageQuadBuffer {
+            bound : Bounds::new(image.bound.center, quad_size, image.bound.z_index),
+            tint : image.tint,
+            uv_min : (atlas_image.min + uv_min * atlas_image.size) / atlas_dims,
+            uv_max : (atlas_image.min + (uv_min + uv_size) * atlas_image.size) / atlas_dims,
+            layer : atlas_image.layer,
+            border_radius : image.border_radius,
+            clip : image.clip,
+        });
+    }
+
+    /// Finds the nine-slice image in the atlas, and resolves its slice insets to image pixels.
+    pub(crate) fn layout_nine_slice(&self, nine_slice : &NineSlice) -> Option<NineSliceBuffer> {
+        let atlas_image = self.images.get(&nine_slice.image)?;
+        let atlas_dims = Vec2::splat(IMAGE_ATLAS_DIMS as f32);
+        let [top, right, bottom, left] = nine_slice.slice;
+
+        Some(NineSliceBuffer {
+            uv_min : atlas_image.min / atlas_dims,
+            uv_max : (atlas_image.min + atlas_image.size) / atlas_dims,
+            layer : atlas_image.layer,
+            image_size : atlas_image.size,
+            slice : Vec4::new(
+                top.to_pixels(atlas_image.size.y),
+                right.to_pixels(atlas_image.size.x),
+                bottom.to_pixels(atlas_image.size.y),
+                left.to_pixels(atlas_image.size.x),
+            ),
+            scale : nine_slice.scale.max(0.0001),
+            edge_mode : nine_slice.edge_mode as u32,
+            center_mode : nine_slice.center_mode as u32,
+        })
+    }
+}
+
+impl FromWorld for SaikoGPUImageAtlas {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+
+        let texture = render_device.create_texture(&TextureDescriptor {
+            label: Some("SaikoImageAtlas"),
+            size: Extent3d {
+                width: IMAGE_ATLAS_DIMS,
+                height: IMAGE_ATLAS_DIMS,
+                depth_or_array_layers: IMAGE_ATLAS_LAYERS,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::bevy_default(),
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+
+        let texture_view = texture.create_view(&TextureViewDescriptor {
+            label: Some("SaikoImageAtlas View"),
+            dimension: Some(TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+
+        let layer_views = (0..IMAGE_ATLAS_LAYERS)
+            .map(|layer| texture.create_view(&TextureViewDescriptor {
+                label: Some("SaikoImageAtlas Layer View"),
+                dimension: Some(TextureViewDimension::D2),
+                base_array_layer: layer,
+                array_layer_count: Some(1),
+                ..Default::default()
+            }))
+            .collect();
+
+        let atlas_sampler = render_device.create_sampler(&SamplerDescriptor {
+            label: Some("SaikoImageAtlas Sampler"),
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let bind_group = render_device.create_bind_group(
+            "saiko_image_atlas_bind_group",
+            &Self::bind_group_layout(render_device),
+            &BindGroupEntries::sequential((&texture_view, &atlas_sampler)),
+        );
+
+        let upload_sampler = render_device.create_sampler(&SamplerDescriptor {
+            label: Some("SaikoImageAtlas Upload Sampler"),
+            ..Default::default()
+        });
+
+        let upload_bind_group_layout = render_device.create_bind_group_layout(
+            "saiko_image_upload_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::FRAGMENT,
+                (
+                    texture_2d(TextureSampleType::Float { filterable: false }),
+                    sampler(SamplerBindingType::NonFiltering),
+                ),
+            ),
+        );
+
+        // The atlas stores premultiplied colors, so filtering does not darken transparent edges.
+        let upload_pipeline = world.resource::<PipelineCache>().queue_render_pipeline(RenderPipelineDescriptor {
+            label: Some("SaikoUI Image Upload Pipeline".into()),
+            layout: vec![upload_bind_group_layout.clone()],
+            vertex: fullscreen_shader_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: BLIT_SHADER_HANDLE,
+                shader_defs: vec![],
+                entry_point: "fs_premultiply".into(),
+                targets: vec![Some(ColorTargetState {
+                    format: TextureFormat::bevy_default(),
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState::default(),
+            multisample: MultisampleState::default(),
+            push_constant_ranges: vec![],
+            depth_stencil: None,
+        });
+
+        Self {
+            layer_views,
+            allocators : (0..IMAGE_ATLAS_LAYERS)
+                .map(|_| AtlasAllocator::new(Size::splat(IMAGE_ATLAS_DIMS as i32)))
+                .collect(),
+            images : HashMap::new(),
+            oversized : HashSet::new(),
+            bind_group,
+            upload_pipeline,
+            upload_bind_group_layout,
+            upload_sampler,
+        }
+    }
+}
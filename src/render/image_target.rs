@@ -0,0 +1,183 @@
+//==============================================================================
+//  Image targets draw a ui subtree into an image instead of onto a camera,
+//  so the image can be used as a texture anywhere in the world. Like the
+//  cameras, an image target is only redrawn when its subtree changes, and
+//  the image keeps what was last drawn into it until then.
+//==============================================================================
+
+use bevy::{
+    prelude::*,
+    render::{
+        graph::CameraDriverLabel,
+        render_asset::{RenderAssetUsages, RenderAssets},
+        render_graph::{Node, NodeRunError, RenderGraph, RenderGraphContext, RenderLabel},
+        render_resource::{Extent3d, TextureDimension, TextureFormat, TextureUsages},
+        renderer::RenderContext,
+        texture::BevyDefault,
+        Extract, RenderApp,
+    },
+};
+
+use crate::common::MarkSaikoUiDirty;
+
+use super::{
+    buffer::{SaikoBuffer, SaikoPreparedBuffer},
+    pass::render_buffer,
+    SaikoRenderTarget,
+};
+
+//==============================================================================
+//             SaikoImageTargetPlugin
+//==============================================================================
+
+pub struct SaikoImageTargetPlugin;
+
+impl Plugin for SaikoImageTargetPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(First, reset_image_targets)
+            .add_systems(Last, mark_image_targets_dirty)
+        ;
+
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app.add_systems(ExtractSchedule, extract_image_targets.before(apply_deferred));
+
+        // Image targets are drawn before any camera, so the cameras see the new image this frame.
+        let image_target_node = SaikoImageTargetNode::from_world(&mut render_app.world);
+        let mut graph = render_app.world.resource_mut::<RenderGraph>();
+        graph.add_node(SaikoImageTargetLabel, image_target_node);
+        graph.add_node_edge(SaikoImageTargetLabel, CameraDriverLabel);
+    }
+}
+
+//==============================================================================
+//             SaikoImageTarget
+//==============================================================================
+
+/// Draws the ui nodes under this entity into an image, instead of onto the cameras. The nodes
+/// are laid out inside of the image like they would be inside of the window.
+///
+/// The image has to be created with [`SaikoImageTarget::create_image`], so it can be rendered to.
+/// Backdrop blur is not drawn into image targets, since there is no scene behind them.
+#[derive(Component, Clone)]
+pub struct SaikoImageTarget {
+    pub image : Handle<Image>,
+    is_dirty : bool,
+}
+
+impl SaikoImageTarget {
+    pub fn new(image : Handle<Image>) -> Self {
+        Self { image, is_dirty : true }
+    }
+
+    /// Creates an empty image that the ui can be drawn into, at a fixed resolution.
+    pub fn create_image(width : u32, height : u32) -> Image {
+        let mut image = Image::new_fill(
+            Extent3d { width, height, depth_or_array_layers : 1 },
+            TextureDimension::D2,
+            &[0, 0, 0, 0],
+            TextureFormat::bevy_default(),
+            RenderAssetUsages::default(),
+        );
+        image.texture_descriptor.usage = TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST | TextureUsages::RENDER_ATTACHMENT;
+        image
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.is_dirty
+    }
+
+    /// Redraws the image at the end of this frame.
+    pub fn mark_dirty(&mut self) {
+        self.is_dirty = true;
+    }
+}
+
+//==============================================================================
+//             SaikoImageTarget Systems
+//==============================================================================
+
+fn reset_image_targets(mut image_targets : Query<&mut SaikoImageTarget>) {
+    for mut image_target in image_targets.iter_mut() {
+        image_target.bypass_change_detection().is_dirty = false;
+    }
+}
+
+/// Image targets are redrawn when they are changed, and when the whole ui is marked dirty, which
+/// is how assets ask to be redrawn once they are loaded.
+fn mark_image_targets_dirty(
+    mut image_targets : Query<&mut SaikoImageTarget>,
+    mut dirty : EventReader<MarkSaikoUiDirty>,
+) {
+    let ui_dirty = dirty.read().count() > 0;
+
+    for mut image_target in image_targets.iter_mut() {
+        if ui_dirty || image_target.is_changed() {
+            image_target.bypass_change_detection().is_dirty = true;
+        }
+    }
+}
+
+/// The image that a render target draws into, when it is not a camera.
+#[derive(Component)]
+pub(crate) struct SaikoExtractedImageTarget {
+    pub(crate) image : AssetId<Image>,
+}
+
+fn extract_image_targets(
+    mut commands : Commands,
+    image_targets : Extract<Query<(Entity, &SaikoImageTarget)>>,
+) {
+    for (entity, image_target) in image_targets.iter() {
+        if !image_target.is_dirty() { continue }
+
+        commands.get_or_spawn(entity).insert((
+            // The image has no window, so a logical pixel is a pixel of the image.
+            SaikoRenderTarget { render_layers : None, buffer : SaikoBuffer::new(1.0), root : Some(entity), window : None },
+            SaikoExtractedImageTarget { image : image_target.image.id() },
+        ));
+    }
+}
+
+//==============================================================================
+//             SaikoImageTargetNode
+//==============================================================================
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+pub struct SaikoImageTargetLabel;
+
+/// Draws every image target that was prepared this frame straight into its image.
+struct SaikoImageTargetNode {
+    image_targets : QueryState<(Entity, &'static SaikoPreparedBuffer, &'static SaikoExtractedImageTarget)>,
+}
+
+impl FromWorld for SaikoImageTargetNode {
+    fn from_world(world: &mut World) -> Self {
+        Self { image_targets : world.query() }
+    }
+}
+
+impl Node for SaikoImageTargetNode {
+    fn update(&mut self, world: &mut World) {
+        self.image_targets.update_archetypes(world);
+    }
+
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let images = world.resource::<RenderAssets<Image>>();
+
+        for (entity, prepared_buffer, image_target) in self.image_targets.iter_manual(world) {
+            let Some(image) = images.get(image_target.image) else { continue };
+            render_buffer(world, render_context, entity, prepared_buffer, &image.texture_view);
+        }
+
+        Ok(())
+    }
+}
@@ -1,5 +1,10 @@
+pub mod backdrop;
 pub mod buffer;
 pub mod font;
+pub mod image;
+pub mod image_target;
+pub mod path;
+pub mod svg;
 mod pass;
 mod pipeline;
 
@@ -9,21 +14,26 @@ use bevy::{
         core_2d::graph::{Core2d, Node2d},
         core_3d::graph::{Core3d, Node3d},
     },
+    ecs::query::QueryItem,
     prelude::*,
     render::{
+        camera::NormalizedRenderTarget,
+        extract_component::{ExtractComponent, ExtractComponentPlugin},
         render_asset::RenderAssets,
         render_graph::{RenderGraph, RunGraphOnViewNode, ViewNodeRunner},
-        render_resource::AsBindGroup,
-        renderer::RenderDevice,
+        render_resource::{AsBindGroup, BindGroupEntries, PipelineCache},
+        renderer::{RenderDevice, RenderQueue},
         view::{RenderLayers, ViewTarget},
         Extract, Render, RenderApp, RenderSet,
-    }, window::WindowResized,
+    },
+    utils::HashSet,
+    window::{PrimaryWindow, WindowResized, WindowScaleFactorChanged},
 };
 
 use crate::{
     common::MarkSaikoUiDirty,
     render::{
-        font::SaikoFontPlugin, pass::{SaikoRenderLabel, SaikoSubGraph}, pipeline::{SaikoRenderPipeline, SaikoRenderPipelinePlugin}
+        backdrop::{SaikoBackdropPlugin, SaikoBackdrops}, font::{sdf::SaikoGPUFontAtlas, SaikoFontPlugin}, image::{SaikoGPUImageAtlas, SaikoImagePlugin}, image_target::{SaikoExtractedImageTarget, SaikoImageTargetPlugin}, pass::{SaikoRenderLabel, SaikoSubGraph}, pipeline::{SaikoRenderPipeline, SaikoRenderPipelinePlugin}, svg::{SaikoSvgIcons, SaikoSvgPlugin}
     },
 };
 
@@ -37,7 +47,11 @@ use self::{
 //  renderer that has been made for the Zed editor called GPUI. Here is
 //  a link to a blog post about their rendering: https://zed.dev/blog/videogame
 //
-//  TODO: Rewrite the following line
+//  Like GPUI, every primitive is drawn as an instanced quad that only covers
+//  its own bounds, and the fragment shader evaluates the sdf of that single
+//  primitive. The cost of a frame is the area the primitives cover, not the
+//  screen size times the primitive count, so there is no need to bin the
+//  primitives into screen tiles like a fullscreen pass would.
 //
 //  The following are the RenderingNode and the RenderPipeline.
 //  The RenderingNode is a struct that defines how the ui is renderded in
@@ -48,6 +62,7 @@ use self::{
 pub const SAIKO_SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(11079037277321826659);
 pub const SAIKO_STYLE_HANDLE: Handle<Shader> = Handle::weak_from_u128(12382237267321826659);
 pub const BLIT_SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(11079030077321896629);
+pub const BACKDROP_SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(11079030077329183652);
 
 //==============================================================================
 //             SaikoRenderPlugin
@@ -60,12 +75,21 @@ impl Plugin for SaikoRenderPlugin {
         load_internal_asset!(app, SAIKO_SHADER_HANDLE, "saiko.wgsl", Shader::from_wgsl);
         load_internal_asset!(app, SAIKO_STYLE_HANDLE, "style.wgsl", Shader::from_wgsl);
         load_internal_asset!(app, BLIT_SHADER_HANDLE, "blit.wgsl", Shader::from_wgsl);
+        load_internal_asset!(app, BACKDROP_SHADER_HANDLE, "backdrop.wgsl", Shader::from_wgsl);
 
-        app.init_resource::<SaikoRenderState>();
+        app
+            .init_resource::<SaikoRenderState>()
+            .register_type::<SaikoCamera>()
+            .register_type::<SaikoWindowRoot>();
 
         app
+            .add_plugins(ExtractComponentPlugin::<SaikoCamera>::default())
             .add_plugins(SaikoRenderPipelinePlugin)
             .add_plugins(SaikoFontPlugin)
+            .add_plugins(SaikoImagePlugin)
+            .add_plugins(SaikoBackdropPlugin)
+            .add_plugins(SaikoSvgPlugin)
+            .add_plugins(SaikoImageTargetPlugin)
             .add_systems(First, reset_saiko_render_state)
             .add_systems(Last, update_saiko_render_state);
 
@@ -117,81 +141,206 @@ fn get_ui_graph(render_app: &mut App) -> RenderGraph {
 //             SaikoRenderIsDirty
 //==============================================================================
 
+/// Which of the ui has to be redrawn this frame. Each window is redrawn on its own, so a change
+/// in a tool window does not redraw the game window.
 #[derive(Resource)]
 pub struct SaikoRenderState {
     is_dirty: bool,
+    dirty_windows: HashSet<Entity>,
 }
 
 impl Default for SaikoRenderState {
     fn default() -> Self {
-        Self { is_dirty: true }
+        Self { is_dirty: true, dirty_windows: HashSet::new() }
     }
 }
 
 impl SaikoRenderState {
+    /// Whether any of the ui has to be redrawn.
     pub fn is_dirty(&self) -> bool {
-        self.is_dirty
+        self.is_dirty || !self.dirty_windows.is_empty()
+    }
+    
+    /// Whether the ui in the window has to be redrawn.
+    pub fn is_window_dirty(&self, window: Entity) -> bool {
+        self.is_dirty || self.dirty_windows.contains(&window)
     }
     
+    /// Redraws the ui in every window.
     pub fn mark_dirty(&mut self) {
         self.is_dirty = true;
     }
+    
+    /// Redraws only the ui in the window.
+    pub fn mark_window_dirty(&mut self, window: Entity) {
+        self.dirty_windows.insert(window);
+    }
 }
 
 fn update_saiko_render_state(
     mut state: ResMut<SaikoRenderState>,
     dirty: EventReader<MarkSaikoUiDirty>,
-    window_resized_event : EventReader<WindowResized>
+    mut window_resized : EventReader<WindowResized>,
+    mut scale_factor_changed : EventReader<WindowScaleFactorChanged>,
 ) {
-    if !dirty.is_empty() || !window_resized_event.is_empty() {
+    if !dirty.is_empty() {
         state.is_dirty = true;
     }
+    
+    let windows = window_resized
+        .read()
+        .map(|event| event.window)
+        .chain(scale_factor_changed.read().map(|event| event.window));
+    for window in windows {
+        state.mark_window_dirty(window);
+    }
 }
 
 fn reset_saiko_render_state(mut state: ResMut<SaikoRenderState>) {
     state.is_dirty = false;
+    state.dirty_windows.clear();
+}
+
+//==============================================================================
+//             SaikoCamera
+//==============================================================================
+
+/// Draws the ui on this camera. Cameras without it never draw any ui.
+#[derive(Component, Clone, Default, Reflect)]
+#[reflect(Component)]
+pub struct SaikoCamera {
+    /// Only draws the nodes under this entity, ignoring render layers. When this is `None`, the
+    /// camera draws every node that is not under another camera's root or an image target.
+    ///
+    /// Nodes without a root are laid out in the viewport of the first camera without a root in
+    /// the primary window, so split screen cameras each need their own root to get ui that fits
+    /// their viewport.
+    pub root: Option<Entity>,
+}
+
+impl SaikoCamera {
+    /// A camera that only draws the node tree under `root`.
+    pub fn with_root(root: Entity) -> Self {
+        Self { root: Some(root) }
+    }
+}
+
+impl ExtractComponent for SaikoCamera {
+    type QueryData = &'static Self;
+    type QueryFilter = With<Camera>;
+    type Out = Self;
+
+    fn extract_component(camera: QueryItem<'_, Self::QueryData>) -> Option<Self> {
+        Some(camera.clone())
+    }
+}
+
+/// The window that a camera renders to, if it renders to a window at all.
+pub(crate) fn camera_window(camera: &Camera, primary_window: Option<Entity>) -> Option<Entity> {
+    match camera.target.normalize(primary_window)? {
+        NormalizedRenderTarget::Window(window) => Some(window.entity()),
+        _ => None,
+    }
+}
+
+//==============================================================================
+//             SaikoWindowRoot
+//==============================================================================
+
+/// Attaches the node tree under this entity to a window. The tree is laid out in the window, and
+/// drawn by every [`SaikoCamera`] without a root that renders to it. Nodes that are not under
+/// any root belong to the primary window.
+#[derive(Component, Clone, Copy, Reflect)]
+pub struct SaikoWindowRoot {
+    pub window: Entity,
+}
+
+impl SaikoWindowRoot {
+    pub fn new(window: Entity) -> Self {
+        Self { window }
+    }
 }
 
 //==============================================================================
 //             SaikoRenderTarget
 //==============================================================================
 
+/// The buffer that the ui is drawn into for a camera or an image target.
 #[derive(Component)]
-pub struct SaikoRenderTarget(pub Option<RenderLayers>, pub SaikoBuffer);
+pub struct SaikoRenderTarget {
+    pub render_layers : Option<RenderLayers>,
+    pub buffer : SaikoBuffer,
+    /// The entity whose subtree is the only ui drawn into the target.
+    pub root : Option<Entity>,
+    /// Targets without a root draw the nodes of this window. It is `None` for targets that are
+    /// not in a window.
+    pub window : Option<Entity>,
+}
 
 //==============================================================================
 //             SaikoUi Render Systems
 //==============================================================================
 
+/// Cameras are redrawn when the ui in their window changes, and when the camera itself changes,
+/// which includes when it is added and when its window is resized.
 fn extract_cameras_for_render(
     mut commands: Commands,
-    mut has_initialized: Local<bool>,
-    cameras: Extract<Query<(Entity, Option<&RenderLayers>), With<Camera>>>,
+    cameras: Extract<Query<(Entity, Ref<Camera>, &SaikoCamera, Option<&RenderLayers>)>>,
+    primary_window: Extract<Query<Entity, With<PrimaryWindow>>>,
     ui_dirty: Extract<Res<SaikoRenderState>>,
 ) {
-    if ui_dirty.is_dirty() || !*has_initialized {
-        for (entity, render_layers) in cameras.iter() {
-            let mut cam_entity = commands.get_or_spawn(entity);
-            let render_layers = render_layers.map(|value| value.clone());
-            cam_entity.insert(SaikoRenderTarget(render_layers, SaikoBuffer::default()));
-        }
-
-        *has_initialized = true;
+    let primary_window = primary_window.get_single().ok();
+    
+    for (entity, camera, saiko_camera, render_layers) in cameras.iter() {
+        let window = camera_window(&camera, primary_window);
+        let is_dirty = match window {
+            Some(window) => ui_dirty.is_window_dirty(window),
+            None => ui_dirty.is_dirty(),
+        };
+        if !is_dirty && !camera.is_changed() { continue }
+        
+        let mut cam_entity = commands.get_or_spawn(entity);
+        let render_layers = render_layers.map(|value| value.clone());
+        let buffer = SaikoBuffer::new(camera.target_scaling_factor().unwrap_or(1.0));
+        cam_entity.insert(SaikoRenderTarget { render_layers, buffer, root : saiko_camera.root, window });
     }
 }
 
 fn prepare_prepare_bind_groups(
     mut commands: Commands,
-    mut render_targets: Query<(Entity, &mut SaikoRenderTarget, &ViewTarget)>,
-    saiko_pipeline: ResMut<SaikoRenderPipeline>,
+    mut render_targets: Query<(Entity, &mut SaikoRenderTarget, Option<&ViewTarget>, Option<&SaikoExtractedImageTarget>)>,
+    mut saiko_pipeline: ResMut<SaikoRenderPipeline>,
+    font_atlas: Res<SaikoGPUFontAtlas>,
+    icons: Res<SaikoSvgIcons>,
+    mut image_atlas: ResMut<SaikoGPUImageAtlas>,
+    mut backdrops: ResMut<SaikoBackdrops>,
     images: Res<RenderAssets<Image>>,
+    pipeline_cache: Res<PipelineCache>,
     render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
 ) {
-    for (render_target_entity, mut render_target, view_target) in render_targets.iter_mut() {
+    for (render_target_entity, mut render_target, view_target, image_target) in render_targets.iter_mut() {
         println!("Preparing render texture");
-        render_target.1.screen_size = Vec2::new(view_target.main_texture().width() as f32, view_target.main_texture().height() as f32);
+        let (width, height) = match (view_target, image_target) {
+            (Some(view_target), _) => (view_target.main_texture().width(), view_target.main_texture().height()),
+            (None, Some(image_target)) => match images.get(image_target.image) {
+                Some(image) => (image.size.x as u32, image.size.y as u32),
+                None => continue,
+            },
+            (None, None) => continue,
+        };
+        render_target.buffer.screen_size = Vec2::new(width as f32, height as f32);
         
-        let Ok(prepared_bind_group) = render_target.1.as_bind_group(
+        image_atlas.upload(
+            render_target.buffer.image_ids(),
+            &images,
+            &pipeline_cache,
+            &render_device,
+            &render_queue,
+        );
+        render_target.buffer.prepare(&font_atlas, &image_atlas, &icons);
+        
+        let Ok(prepared_bind_group) = render_target.buffer.as_bind_group(
             &saiko_pipeline.bind_group_layout,
             render_device.as_ref(),
             images.as_ref(),
@@ -200,8 +349,29 @@ fn prepare_prepare_bind_groups(
             continue;
         };
         
+        //Backdrops blur the scene behind a camera's ui, so image targets have none.
+        if view_target.is_some() {
+            let scale_factor = render_target.buffer.scale_factor;
+            backdrops.prepare_view(render_target_entity, &render_target.buffer.backdrops, &render_target.buffer.clips, width, height, scale_factor, &render_device, &render_queue);
+        }
+        
+        let layer_bind_group_layout = saiko_pipeline.layer_bind_group_layout.clone();
+        let layer_bind_groups = saiko_pipeline
+            .layer_textures(render_target_entity, render_target.buffer.layers.len(), width, height, &render_device)
+            .iter()
+            .map(|texture| render_device.create_bind_group(
+                "saiko_layer_bind_group",
+                &layer_bind_group_layout,
+                &BindGroupEntries::single(texture),
+            ))
+            .collect();
+        
         commands
             .entity(render_target_entity)
-            .insert(SaikoPreparedBuffer(prepared_bind_group));
+            .insert(SaikoPreparedBuffer {
+                bind_group: prepared_bind_group,
+                batches: render_target.buffer.batches.clone(),
+                layer_bind_groups,
+            });
     }
 }
@@ -1,16 +1,17 @@
 use bevy::core_pipeline::blit::BlitPipeline;
 use bevy::prelude::*;
 use bevy::render::render_graph::{RenderLabel, RenderSubGraph};
-use bevy::render::render_resource::{BindGroupEntries, Operations, RenderPassColorAttachment};
+use bevy::render::render_resource::{BindGroupEntries, Operations, RenderPassColorAttachment, TextureView};
+use bevy::render::renderer::RenderContext;
 use bevy::render::{
     render_graph::ViewNode,
     render_resource::{PipelineCache, RenderPassDescriptor},
     view::ViewTarget,
 };
 
-use crate::render::pipeline::SaikoRenderPipeline;
+use crate::render::{SaikoCamera, backdrop::SaikoBackdrops, font::sdf::SaikoGPUFontAtlas, image::SaikoGPUImageAtlas, pipeline::SaikoRenderPipeline};
 
-use super::buffer::SaikoPreparedBuffer;
+use super::buffer::{SaikoPreparedBuffer, SaikoPrimitive};
 
 //==============================================================================
 //             SaikoRenderNode
@@ -30,6 +31,8 @@ impl ViewNode for SaikoRenderNode {
         Entity,
         &'static ViewTarget,
         Option<&'static SaikoPreparedBuffer>,
+        //Only cameras marked with SaikoCamera draw the ui.
+        &'static SaikoCamera,
     );
 
     fn run<'w>(
@@ -39,21 +42,11 @@ impl ViewNode for SaikoRenderNode {
         view_query: bevy::ecs::query::QueryItem<'w, Self::ViewQuery>,
         world: &'w World,
     ) -> Result<(), bevy::render::render_graph::NodeRunError> {
-        let (entity, view_target, prepared_buffer) = view_query;
+        let (entity, view_target, prepared_buffer, _) = view_query;
 
-        //Get Pipelines from Resources
         let saiko_pipeline_resource = world.resource::<SaikoRenderPipeline>();
-
-        //Get the pipeline cache
         let pipeline_cache = world.resource::<PipelineCache>();
 
-        //Get the pipeline from the pipeline cache
-        let Some(saiko_pipeline) =
-            pipeline_cache.get_render_pipeline(saiko_pipeline_resource.pipeline)
-        else {
-            return Ok(());
-        };
-
         let Some(blit_pipeline) =
             pipeline_cache.get_render_pipeline(saiko_pipeline_resource.blit_pipeline)
         else {
@@ -66,34 +59,16 @@ impl ViewNode for SaikoRenderNode {
             .unwrap();
 
         //If the shaping data has been loaded into the bind group, render it
-        // if let Some(prepared_bind_group) = &saiko_pipeline_resource.bind_groups {
         if let Some(prepared_buffer) = prepared_buffer {
             println!("Rendering to texture!");
-            //Get the bind group from the prepared buffer
-            let bind_group = &prepared_buffer.0.bind_group;
-
-            //Create the render pass. This is what will render the final result.
-            let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
-                label: "SaikoUI Render Pass".into(),
-                // color_attachments: &[Some(view_target.get_color_attachment())],
-                color_attachments: &[Some(RenderPassColorAttachment {
-                    view: render_texture,
-                    resolve_target: None,
-                    ops: Operations::default(),
-                })],
-                depth_stencil_attachment: None,
-                timestamp_writes: None,
-                occlusion_query_set: None,
-            });
-
-            //Set the pipeline to be rendered and attach the bind group
-            render_pass.set_render_pipeline(saiko_pipeline);
-            render_pass.set_bind_group(0, bind_group, &[]);
-
-            //Send it baby!
-            render_pass.draw(0..3, 0..1);
+            render_buffer(world, render_context, entity, prepared_buffer, render_texture);
         }
 
+        //The backdrops blur the scene itself, so they are drawn every frame under the ui.
+        world
+            .resource::<SaikoBackdrops>()
+            .render(entity, view_target, pipeline_cache, render_context);
+
         let blit_pipeline_resource = world.resource::<BlitPipeline>();
 
         let blit_bind_group = render_context.render_device().create_bind_group(
@@ -118,3 +93,88 @@ impl ViewNode for SaikoRenderNode {
         Ok(())
     }
 }
+
+//==============================================================================
+//             Render Buffer
+//==============================================================================
+
+/// Draws a prepared buffer into `render_texture`. The layers of the buffer are drawn first, into
+/// the layer textures of `entity`, so they are ready when they are drawn into their target.
+pub(crate) fn render_buffer(
+    world: &World,
+    render_context: &mut RenderContext,
+    entity: Entity,
+    prepared_buffer: &SaikoPreparedBuffer,
+    render_texture: &TextureView,
+) {
+    //Get Pipelines from Resources
+    let saiko_pipeline_resource = world.resource::<SaikoRenderPipeline>();
+    let font_atlas = world.resource::<SaikoGPUFontAtlas>();
+    let image_atlas = world.resource::<SaikoGPUImageAtlas>();
+
+    //Get the pipeline cache
+    let pipeline_cache = world.resource::<PipelineCache>();
+
+    //Get the pipelines from the pipeline cache
+    let (Some(rect_pipeline), Some(glyph_pipeline), Some(image_pipeline), Some(layer_pipeline), Some(path_pipeline), Some(ellipse_pipeline)) = (
+        pipeline_cache.get_render_pipeline(saiko_pipeline_resource.rect_pipeline),
+        pipeline_cache.get_render_pipeline(saiko_pipeline_resource.glyph_pipeline),
+        pipeline_cache.get_render_pipeline(saiko_pipeline_resource.image_pipeline),
+        pipeline_cache.get_render_pipeline(saiko_pipeline_resource.layer_pipeline),
+        pipeline_cache.get_render_pipeline(saiko_pipeline_resource.path_pipeline),
+        pipeline_cache.get_render_pipeline(saiko_pipeline_resource.ellipse_pipeline),
+    ) else {
+        return;
+    };
+
+    //Get the bind group from the prepared buffer
+    let bind_group = &prepared_buffer.bind_group.bind_group;
+    let layer_textures = saiko_pipeline_resource
+        .layer_textures
+        .get(&entity)
+        .map_or(&[][..], |(textures, _, _)| textures.as_slice());
+
+    //Layers are drawn first, so their textures are ready when they are drawn into their target.
+    for target in (0..=prepared_buffer.layer_bind_groups.len()).rev() {
+        let target_texture = match target {
+            0 => render_texture,
+            layer => &layer_textures[layer - 1],
+        };
+
+        //Create the render pass. This is what will render the final result.
+        let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+            label: "SaikoUI Render Pass".into(),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: target_texture,
+                resolve_target: None,
+                ops: Operations::default(),
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        render_pass.set_bind_group(0, bind_group, &[]);
+        render_pass.set_bind_group(1, font_atlas.bind_group(), &[]);
+        render_pass.set_bind_group(2, image_atlas.bind_group(), &[]);
+
+        //Every primitive is a quad made of 6 vertices, one instance per primitive.
+        for batch in prepared_buffer.batches.iter().filter(|batch| batch.target as usize == target) {
+            let pipeline = match batch.primitive {
+                SaikoPrimitive::Rect => rect_pipeline,
+                SaikoPrimitive::Glyph => glyph_pipeline,
+                SaikoPrimitive::Image => image_pipeline,
+                SaikoPrimitive::Path => path_pipeline,
+                SaikoPrimitive::Ellipse => ellipse_pipeline,
+                SaikoPrimitive::Layer => {
+                    let layer_bind_group = &prepared_buffer.layer_bind_groups[batch.instances.start as usize];
+                    render_pass.set_bind_group(3, layer_bind_group, &[]);
+                    layer_pipeline
+                },
+            };
+
+            render_pass.set_render_pipeline(pipeline);
+            render_pass.draw(0..6, batch.instances.clone());
+        }
+    }
+}
@@ -0,0 +1,285 @@
+//==============================================================================
+//  Paths are built out of quadratic curves, the same way glyph outlines are.
+//  Each path is drawn as a single quad that measures the distance to all of
+//  its curves, so overlapping curves never blend over each other. Strokes
+//  have butt ends where two curves meet, and the join between them is added
+//  as its own shape.
+//==============================================================================
+
+use bevy::{math::Rect, prelude::*};
+
+use crate::common::bounds::Bounds;
+
+use super::{
+    buffer::{LineCap, LineJoin, PathBuffer, PathSegmentBuffer, PathSegmentKind},
+    font::GlyphCurve,
+};
+
+/// How many quadratic curves a cubic curve is split into.
+const CUBIC_SUBDIVISIONS : usize = 4;
+
+/// How far a miter join can reach past the stroke, as a multiple of half the stroke width,
+/// before it is drawn as a bevel instead.
+const MITER_LIMIT : f32 = 4.0;
+
+//==============================================================================
+//             SaikoPath
+//==============================================================================
+
+/// The outline of a path. Points are in pixels, relative to the center of the bounds the path
+/// is drawn in, with y pointing up.
+#[derive(Debug, Clone, Default)]
+pub struct SaikoPath {
+    subpaths : Vec<SaikoSubpath>,
+    current_pos : Vec2,
+}
+
+#[derive(Debug, Clone, Default)]
+struct SaikoSubpath {
+    curves : Vec<GlyphCurve>,
+    closed : bool,
+}
+
+impl SaikoPath {
+    /// Starts a new subpath at the point.
+    pub fn move_to(&mut self, point : impl Into<Vec2>) {
+        self.current_pos = point.into();
+        self.subpaths.push(SaikoSubpath::default());
+    }
+
+    pub fn line_to(&mut self, point : impl Into<Vec2>) {
+        let point = point.into();
+        let curve = GlyphCurve::line(self.current_pos, point);
+        self.push_curve(curve);
+    }
+
+    pub fn quad_to(&mut self, control : impl Into<Vec2>, point : impl Into<Vec2>) {
+        let curve = GlyphCurve {
+            start : self.current_pos,
+            control : control.into(),
+            end : point.into(),
+        };
+        self.push_curve(curve);
+    }
+
+    pub fn cubic_to(&mut self, control_1 : impl Into<Vec2>, control_2 : impl Into<Vec2>, point : impl Into<Vec2>) {
+        let points = [self.current_pos, control_1.into(), control_2.into(), point.into()];
+
+        // Each piece of the cubic is short enough to be close to a single quadratic curve.
+        for index in 0..CUBIC_SUBDIVISIONS {
+            let t0 = index as f32 / CUBIC_SUBDIVISIONS as f32;
+            let t1 = (index + 1) as f32 / CUBIC_SUBDIVISIONS as f32;
+            let [q0, q1, q2, q3] = split_cubic(points, t0, t1);
+            self.push_curve(GlyphCurve::from_cubic(q0, q1, q2, q3));
+        }
+    }
+
+    /// Draws a line back to the start of the subpath, and joins its ends together.
+    pub fn close(&mut self) {
+        let Some(subpath) = self.subpaths.last_mut() else { return };
+        let Some(first) = subpath.curves.first() else { return };
+        let start = first.start;
+
+        if start.distance_squared(self.current_pos) > f32::EPSILON {
+            subpath.curves.push(GlyphCurve::line(self.current_pos, start));
+        }
+        subpath.closed = true;
+        self.current_pos = start;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.subpaths.iter().all(|subpath| subpath.curves.is_empty())
+    }
+
+    /// The same path, scaled around its origin.
+    pub(crate) fn scaled(&self, scale : f32) -> SaikoPath {
+        let subpaths = self.subpaths
+            .iter()
+            .map(|subpath| SaikoSubpath {
+                curves : subpath.curves
+                    .iter()
+                    .map(|curve| GlyphCurve {
+                        start : curve.start * scale,
+                        control : curve.control * scale,
+                        end : curve.end * scale,
+                    })
+                    .collect(),
+                closed : subpath.closed,
+            })
+            .collect();
+
+        SaikoPath { subpaths, current_pos : self.current_pos * scale }
+    }
+
+    /// Where every curve of the path ends, in order.
+    #[cfg(test)]
+    pub(crate) fn curve_ends(&self) -> Vec<Vec2> {
+        self.subpaths.iter().flat_map(|subpath| subpath.curves.iter().map(|curve| curve.end)).collect()
+    }
+
+    fn push_curve(&mut self, curve : GlyphCurve) {
+        // Drawing after a close starts a new subpath from the same point, like svg.
+        match self.subpaths.last_mut() {
+            Some(subpath) if !subpath.closed => subpath.curves.push(curve),
+            _ => self.subpaths.push(SaikoSubpath { curves : vec![curve], closed : false }),
+        }
+        self.current_pos = curve.end;
+    }
+
+    /// Pushes the curves and joins of the path, moved to `offset`, and sizes the bounds of the
+    /// path to cover all of them, including its stroke.
+    pub(crate) fn push_segments(
+        &self,
+        offset : Vec2,
+        path : &mut PathBuffer,
+        line_cap : LineCap,
+        line_join : LineJoin,
+        segments : &mut Vec<PathSegmentBuffer>,
+    ) {
+        let half_width = path.stroke_width * 0.5;
+        let first_point = self
+            .subpaths
+            .iter()
+            .find_map(|subpath| subpath.curves.first())
+            .map_or(offset, |curve| curve.start + offset);
+        let mut area = Rect::from_corners(first_point, first_point);
+        path.segment_start = segments.len() as u32;
+
+        for subpath in self.subpaths.iter().filter(|subpath| !subpath.curves.is_empty()) {
+            let curves = subpath
+                .curves
+                .iter()
+                .map(|curve| GlyphCurve {
+                    start : curve.start + offset,
+                    control : curve.control + offset,
+                    end : curve.end + offset,
+                })
+                .collect::<Vec<_>>();
+
+            for (index, curve) in curves.iter().enumerate() {
+                let is_first = index == 0 && !subpath.closed;
+                let is_last = index == curves.len() - 1 && !subpath.closed;
+
+                segments.push(PathSegmentBuffer {
+                    start : curve.start,
+                    control : curve.control,
+                    end : curve.end,
+                    extra : Vec2::ZERO,
+                    kind : PathSegmentKind::Curve as u32,
+                    start_cap : if is_first { line_cap } else { LineCap::Butt } as u32,
+                    end_cap : if is_last { line_cap } else { LineCap::Butt } as u32,
+                });
+
+                for point in [curve.start, curve.control, curve.end] {
+                    area = area.union_point(point);
+                }
+            }
+
+            // Joins go between every pair of curves, and between the last and the first curve
+            // when the subpath is closed.
+            let joins = curves.len() - 1 + subpath.closed as usize;
+            for index in 0..joins {
+                let incoming = curves[index];
+                let outgoing = curves[(index + 1) % curves.len()];
+                if let Some(join) = join_segment(incoming, outgoing, half_width, line_join) {
+                    area = area.union_point(join.control).union_point(join.end).union_point(join.extra);
+                    segments.push(join);
+                }
+            }
+
+            // Open subpaths are still filled as if they were closed.
+            let first = curves[0].start;
+            let last = curves[curves.len() - 1].end;
+            if !subpath.closed && first.distance_squared(last) > f32::EPSILON {
+                let closing = GlyphCurve::line(last, first);
+                segments.push(PathSegmentBuffer {
+                    start : closing.start,
+                    control : closing.control,
+                    end : closing.end,
+                    extra : Vec2::ZERO,
+                    kind : PathSegmentKind::Close as u32,
+                    start_cap : LineCap::Butt as u32,
+                    end_cap : LineCap::Butt as u32,
+                });
+            }
+        }
+
+        path.segment_count = segments.len() as u32 - path.segment_start;
+
+        // Square and round caps reach half the stroke width past the end of the path.
+        let area = Rect::from_corners(area.min - half_width, area.max + half_width);
+        path.bound = Bounds::new(area.center(), area.half_size(), path.bound.z_index);
+    }
+}
+
+/// The shape that fills the gap on the outside of the corner between two curves.
+fn join_segment(incoming : GlyphCurve, outgoing : GlyphCurve, half_width : f32, line_join : LineJoin) -> Option<PathSegmentBuffer> {
+    let vertex = incoming.end;
+    let in_tangent = (incoming.end - incoming.control).try_normalize()
+        .or((incoming.end - incoming.start).try_normalize())?;
+    let out_tangent = (outgoing.control - outgoing.start).try_normalize()
+        .or((outgoing.end - outgoing.start).try_normalize())?;
+
+    // The curves already meet without a gap when they are going the same way.
+    let cross = in_tangent.perp_dot(out_tangent);
+    if cross.abs() < 1e-4 && in_tangent.dot(out_tangent) > 0.0 {
+        return None;
+    }
+
+    // The gap is on the right of a left turn, and on the left of a right turn.
+    let side = if cross > 0.0 { -1.0 } else { 1.0 };
+    let in_normal = in_tangent.perp() * side;
+    let out_normal = out_tangent.perp() * side;
+    let in_corner = vertex + in_normal * half_width;
+    let out_corner = vertex + out_normal * half_width;
+
+    let bevel = in_corner.lerp(out_corner, 0.5);
+    let tip = match line_join {
+        LineJoin::Round => {
+            return Some(PathSegmentBuffer {
+                start : vertex,
+                control : vertex,
+                end : vertex,
+                extra : vertex,
+                kind : PathSegmentKind::RoundJoin as u32,
+                start_cap : LineCap::Butt as u32,
+                end_cap : LineCap::Butt as u32,
+            });
+        },
+        LineJoin::Bevel => bevel,
+        LineJoin::Miter => {
+            let direction = (in_normal + out_normal).try_normalize();
+            match direction.map(|direction| (direction, direction.dot(in_normal))) {
+                Some((direction, cos)) if cos > 1.0 / MITER_LIMIT => vertex + direction * (half_width / cos),
+                _ => bevel,
+            }
+        },
+    };
+
+    Some(PathSegmentBuffer {
+        start : vertex,
+        control : in_corner,
+        end : tip,
+        extra : out_corner,
+        kind : PathSegmentKind::Join as u32,
+        start_cap : LineCap::Butt as u32,
+        end_cap : LineCap::Butt as u32,
+    })
+}
+
+/// The control points of the part of a cubic curve between `t0` and `t1`.
+fn split_cubic(points : [Vec2; 4], t0 : f32, t1 : f32) -> [Vec2; 4] {
+    let point = |t : f32| {
+        let u = 1.0 - t;
+        points[0] * u * u * u + points[1] * 3.0 * u * u * t + points[2] * 3.0 * u * t * t + points[3] * t * t * t
+    };
+    let derivative = |t : f32| {
+        let u = 1.0 - t;
+        (points[1] - points[0]) * 3.0 * u * u + (points[2] - points[1]) * 6.0 * u * t + (points[3] - points[2]) * 3.0 * t * t
+    };
+
+    let scale = (t1 - t0) / 3.0;
+    let start = point(t0);
+    let end = point(t1);
+    [start, start + derivative(t0) * scale, end - derivative(t1) * scale, end]
+}
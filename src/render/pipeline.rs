@@ -11,7 +11,7 @@ use bevy::{
             MultisampleState, PipelineCache, PrimitiveState, RenderPipelineDescriptor,
             SamplerBindingType, ShaderStages, TextureDescriptor,
             TextureDimension, TextureFormat, TextureSampleType, TextureUsages, TextureView,
-            TextureViewDescriptor,
+            TextureViewDescriptor, VertexState,
         },
         renderer::RenderDevice,
         texture::{BevyDefault, FallbackImage},
@@ -21,7 +21,7 @@ use bevy::{
     utils::HashMap,
 };
 
-use super::{buffer::SaikoBuffer, BLIT_SHADER_HANDLE, SAIKO_SHADER_HANDLE};
+use super::{SaikoCamera, buffer::SaikoBuffer, font::sdf::SaikoGPUFontAtlas, image::SaikoGPUImageAtlas, BLIT_SHADER_HANDLE, SAIKO_SHADER_HANDLE};
 
 //==============================================================================
 //             RenderPipelinePlugin
@@ -56,11 +56,20 @@ impl Plugin for SaikoRenderPipelinePlugin {
 
 #[derive(Resource)]
 pub struct SaikoRenderPipeline {
-    pub(crate) pipeline: CachedRenderPipelineId,
+    pub(crate) rect_pipeline: CachedRenderPipelineId,
+    pub(crate) glyph_pipeline: CachedRenderPipelineId,
+    pub(crate) image_pipeline: CachedRenderPipelineId,
+    pub(crate) layer_pipeline: CachedRenderPipelineId,
+    pub(crate) path_pipeline: CachedRenderPipelineId,
+    pub(crate) ellipse_pipeline: CachedRenderPipelineId,
     pub(crate) blit_pipeline: CachedRenderPipelineId,
     pub(crate) bind_group_layout: BindGroupLayout,
     pub(crate) blit_bind_group_layout: BindGroupLayout,
+    pub(crate) layer_bind_group_layout: BindGroupLayout,
     pub(crate) render_textures: HashMap<Entity, (TextureView, u32, u32)>,
+    /// The textures that layers are drawn into for each view. These are only created once a
+    /// view draws that many layers, and are kept around for the next time it is drawn.
+    pub(crate) layer_textures: HashMap<Entity, (Vec<TextureView>, u32, u32)>,
     pub(crate) fallback_image: FallbackImage,
 }
 
@@ -71,6 +80,8 @@ impl FromWorld for SaikoRenderPipeline {
         
         //This is some weird hacky code to get bind group layouts to work
         let bind_group_layout = SaikoBuffer::bind_group_layout(render_device);
+        let font_bind_group_layout = SaikoGPUFontAtlas::bind_group_layout(render_device);
+        let image_bind_group_layout = SaikoGPUImageAtlas::bind_group_layout(render_device);
         
         let blit_bind_group_layout = render_device.create_bind_group_layout(
             "blit_bind_group_layout",
@@ -83,26 +94,22 @@ impl FromWorld for SaikoRenderPipeline {
             ),
         );
 
-        let pipeline = RenderPipelineDescriptor {
-            label: Some("SaikoUI Render Pipeline".into()),
-            layout: vec![bind_group_layout.clone()],
-            // layout: vec![],
-            vertex: fullscreen_shader_vertex_state(),
-            fragment: Some(FragmentState {
-                shader: SAIKO_SHADER_HANDLE,
-                shader_defs: vec![],
-                entry_point: "fragment".into(),
-                targets: vec![Some(ColorTargetState {
-                    format: TextureFormat::bevy_default(),
-                    blend: Some(BlendState::ALPHA_BLENDING),
-                    write_mask: ColorWrites::ALL,
-                })],
-            }),
-            primitive: PrimitiveState::default(),
-            multisample: MultisampleState::default(),
-            push_constant_ranges: vec![],
-            depth_stencil: None,
-        };
+        let layer_bind_group_layout = render_device.create_bind_group_layout(
+            "saiko_layer_bind_group_layout",
+            &BindGroupLayoutEntries::single(
+                ShaderStages::FRAGMENT,
+                texture_2d(TextureSampleType::Float { filterable: false }),
+            ),
+        );
+
+        let layout = vec![bind_group_layout.clone(), font_bind_group_layout, image_bind_group_layout];
+        let layer_layout = [layout.clone(), vec![layer_bind_group_layout.clone()]].concat();
+        let rect_pipeline = saiko_pipeline_descriptor("SaikoUI Rect Pipeline", "rect", layout.clone());
+        let glyph_pipeline = saiko_pipeline_descriptor("SaikoUI Glyph Pipeline", "glyph", layout.clone());
+        let image_pipeline = saiko_pipeline_descriptor("SaikoUI Image Pipeline", "image", layout.clone());
+        let path_pipeline = saiko_pipeline_descriptor("SaikoUI Path Pipeline", "path", layout.clone());
+        let ellipse_pipeline = saiko_pipeline_descriptor("SaikoUI Ellipse Pipeline", "ellipse", layout);
+        let layer_pipeline = saiko_pipeline_descriptor("SaikoUI Layer Pipeline", "layer", layer_layout);
 
         let blit_pipeline = RenderPipelineDescriptor {
             label: Some("SaikoUI Blit Pipeline".into()),
@@ -114,7 +121,8 @@ impl FromWorld for SaikoRenderPipeline {
                 entry_point: "fs_main".into(),
                 targets: vec![Some(ColorTargetState {
                     format: TextureFormat::bevy_default(),
-                    blend: Some(BlendState::ALPHA_BLENDING),
+                    // The ui texture is premultiplied, so it is composited over the scene as is.
+                    blend: Some(BlendState::PREMULTIPLIED_ALPHA_BLENDING),
                     write_mask: ColorWrites::ALL,
                 })],
             }),
@@ -125,25 +133,124 @@ impl FromWorld for SaikoRenderPipeline {
         };
 
         // This will add the pipeline to the cache and queue it's creation
-        let pipeline = world
+        let rect_pipeline = world
+            .resource::<PipelineCache>()
+            .queue_render_pipeline(rect_pipeline);
+        let glyph_pipeline = world
             .resource::<PipelineCache>()
-            .queue_render_pipeline(pipeline);
+            .queue_render_pipeline(glyph_pipeline);
+        let image_pipeline = world
+            .resource::<PipelineCache>()
+            .queue_render_pipeline(image_pipeline);
+        let layer_pipeline = world
+            .resource::<PipelineCache>()
+            .queue_render_pipeline(layer_pipeline);
+        let path_pipeline = world
+            .resource::<PipelineCache>()
+            .queue_render_pipeline(path_pipeline);
+        let ellipse_pipeline = world
+            .resource::<PipelineCache>()
+            .queue_render_pipeline(ellipse_pipeline);
         let blit_pipeline = world
             .resource::<PipelineCache>()
             .queue_render_pipeline(blit_pipeline);
 
         //This is where I would create bind group layouts if I had them
         SaikoRenderPipeline {
-            pipeline,
+            rect_pipeline,
+            glyph_pipeline,
+            image_pipeline,
+            layer_pipeline,
+            path_pipeline,
+            ellipse_pipeline,
             blit_pipeline,
             bind_group_layout,
             blit_bind_group_layout,
+            layer_bind_group_layout,
             render_textures: HashMap::new(),
+            layer_textures: HashMap::new(),
             fallback_image,
         }
     }
 }
 
+impl SaikoRenderPipeline {
+    /// Makes sure the view has at least `count` layer textures of the given size, and returns them.
+    pub(crate) fn layer_textures(
+        &mut self,
+        view_entity : Entity,
+        count : usize,
+        width : u32,
+        height : u32,
+        render_device : &RenderDevice,
+    ) -> &[TextureView] {
+        let (textures, current_width, current_height) = self
+            .layer_textures
+            .entry(view_entity)
+            .or_insert_with(|| (Vec::new(), width, height));
+
+        if *current_width != width || *current_height != height {
+            textures.clear();
+            *current_width = width;
+            *current_height = height;
+        }
+
+        while textures.len() < count {
+            let texture = render_device.create_texture(&TextureDescriptor {
+                label: Some(format!("SaikoUI Layer Texture {:?} {}", view_entity, textures.len()).as_str()),
+                size: Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: TextureFormat::bevy_default(),
+                usage: TextureUsages::TEXTURE_BINDING | TextureUsages::RENDER_ATTACHMENT,
+                view_formats: &[TextureFormat::bevy_default()],
+            });
+
+            textures.push(texture.create_view(&TextureViewDescriptor::default()));
+        }
+
+        &textures[..count]
+    }
+}
+
+/// Every primitive is drawn as instanced quads out of the same shader. The entry points for a
+/// primitive are named `{primitive}_vertex` and `{primitive}_fragment`.
+fn saiko_pipeline_descriptor(
+    label : &'static str,
+    primitive : &str,
+    layout : Vec<BindGroupLayout>,
+) -> RenderPipelineDescriptor {
+    RenderPipelineDescriptor {
+        label: Some(label.into()),
+        layout,
+        vertex: VertexState {
+            shader: SAIKO_SHADER_HANDLE,
+            shader_defs: vec![],
+            entry_point: format!("{primitive}_vertex").into(),
+            buffers: vec![],
+        },
+        fragment: Some(FragmentState {
+            shader: SAIKO_SHADER_HANDLE,
+            shader_defs: vec![],
+            entry_point: format!("{primitive}_fragment").into(),
+            targets: vec![Some(ColorTargetState {
+                format: TextureFormat::bevy_default(),
+                blend: Some(BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                write_mask: ColorWrites::ALL,
+            })],
+        }),
+        primitive: PrimitiveState::default(),
+        multisample: MultisampleState::default(),
+        push_constant_ranges: vec![],
+        depth_stencil: None,
+    }
+}
+
 //==============================================================================
 //             Update Pipeline System
 //==============================================================================
@@ -152,7 +259,7 @@ fn update_pipeline_textures(
     mut pipeline: ResMut<SaikoRenderPipeline>,
     render_device: ResMut<RenderDevice>,
     // render_queue: ResMut<RenderQueue>,
-    view_targets: Query<(Entity, &ViewTarget)>,
+    view_targets: Query<(Entity, &ViewTarget), With<SaikoCamera>>,
 ) {
     for (view_target_entity, view_target) in view_targets.iter() {
         let (current_width, current_height) = pipeline
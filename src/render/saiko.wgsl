@@ -3,58 +3,529 @@
 struct BorderStyle {
     border_color : vec4<f32>,
     border_radius : vec4<f32>,
-    border_width : f32,
+    border_width : vec4<f32>,
+    border_alignment : u32,
+    border_pattern : u32,
+    dash_length : f32,
+    dash_gap : f32,
+}
+
+struct ShadowStyle {
+    shadow_color : vec4<f32>,
+    shadow_offset : vec2<f32>,
+    shadow_blur : f32,
+    shadow_spread : f32,
 }
 
 struct FillStyle {
     fill_color : vec4<f32>,
+    fill_type : u32,
+    gradient_center : vec2<f32>,
+    gradient_angle : f32,
+    stop_count : u32,
+    stop_offsets : vec4<f32>,
+    stop_colors : array<vec4<f32>, 4>,
 }
 
 struct Bound {
     center : vec2<f32>,
     size : vec2<f32>,
-    z_index : f32,
+    z_index : i32,
 }
 
 struct Rect {
     bound : Bound,
     border_style: BorderStyle,
     fill_style: FillStyle,
+    shadow_style: ShadowStyle,
+    inner_shadow_style: ShadowStyle,
+    nine_slice: NineSlice,
+    clip: u32,
 };
 
+struct NineSlice {
+    uv_min : vec2<f32>,
+    uv_max : vec2<f32>,
+    layer : u32,
+    image_size : vec2<f32>,
+    slice : vec4<f32>,
+    scale : f32,
+    edge_mode : u32,
+    center_mode : u32,
+}
+
+struct Glyph {
+    bound : Bound,
+    color : vec4<f32>,
+    uv_min : vec2<f32>,
+    uv_size : vec2<f32>,
+    layer : u32,
+    px_range : f32,
+    clip : u32,
+}
+
+struct ImageQuad {
+    bound : Bound,
+    tint : vec4<f32>,
+    uv_min : vec2<f32>,
+    uv_max : vec2<f32>,
+    layer : u32,
+    border_radius : vec4<f32>,
+    clip : u32,
+}
+
+struct Ellipse {
+    bound : Bound,
+    fill_color : vec4<f32>,
+    stroke_color : vec4<f32>,
+    stroke_width : f32,
+    inner_radius : f32,
+    start_angle : f32,
+    end_angle : f32,
+    clip : u32,
+}
+
+struct Path {
+    bound : Bound,
+    fill_color : vec4<f32>,
+    stroke_color : vec4<f32>,
+    stroke_width : f32,
+    fill_rule : u32,
+    segment_start : u32,
+    segment_count : u32,
+    clip : u32,
+}
+
+struct PathSegment {
+    start : vec2<f32>,
+    control : vec2<f32>,
+    end : vec2<f32>,
+    extra : vec2<f32>,
+    kind : u32,
+    start_cap : u32,
+    end_cap : u32,
+}
+
+struct Layer {
+    bound : Bound,
+    opacity : f32,
+    clip : u32,
+}
+
+struct Clip {
+    bound : Bound,
+    border_radius : vec4<f32>,
+    parent : u32,
+}
+
+struct VertexOutput {
+    @builtin(position) position : vec4<f32>,
+    // The position of the fragment in pixels, relative to the center of the screen with y pointing down.
+    @location(0) point : vec2<f32>,
+    @location(1) @interpolate(flat) instance : u32,
+}
+
 @group(0) @binding(0)
 var<storage, read> rect : array<Rect>;
 @group(0) @binding(1)
 var<uniform> resolution : vec2<f32>;
+@group(0) @binding(2)
+var<storage, read> glyphs : array<Glyph>;
+@group(0) @binding(3)
+var<storage, read> image_quads : array<ImageQuad>;
+@group(0) @binding(4)
+var<storage, read> clips : array<Clip>;
+@group(0) @binding(5)
+var<storage, read> layers : array<Layer>;
+@group(0) @binding(6)
+var<storage, read> paths : array<Path>;
+@group(0) @binding(7)
+var<storage, read> path_segments : array<PathSegment>;
+@group(0) @binding(8)
+var<storage, read> ellipses : array<Ellipse>;
+// Everything is laid out in logical pixels, which are this many physical pixels.
+@group(0) @binding(9)
+var<uniform> scale_factor : f32;
+
+@group(1) @binding(0)
+var font_atlas : texture_2d_array<f32>;
+
+@group(2) @binding(0)
+var image_atlas : texture_2d_array<f32>;
+@group(2) @binding(1)
+var image_sampler : sampler;
+
+// Only bound when drawing a layer. It is the same size as the target, so it is read by pixel.
+@group(3) @binding(0)
+var layer_texture : texture_2d<f32>;
+
+// Extra space around each quad so the edges of a primitive are never cut off by its own quad.
+const QUAD_PADDING : f32 = 1.0;
+
+//==============================================================================
+//             Clip
+//==============================================================================
+
+// How much of the pixel is inside the clip, and every clip it is nested in. A clip index of
+// 0 means the primitive is not clipped.
+fn clip_coverage(point : vec2<f32>, clip_index : u32) -> f32 {
+    var result = 1.0;
+    var index = clip_index;
+    loop {
+        if (index == 0u) {
+            break;
+        }
+        
+        var clip = clips[index];
+        var b : RoundedBox;
+        b.center = clip.bound.center * vec2<f32>(1.0, -1.0);
+        b.half_size = clip.bound.size;
+        b.radius = clip.border_radius;
+        result = min(result, coverage(rounded_box_sdf(point, b)));
+        index = clip.parent;
+    }
+    return result;
+}
+
+//==============================================================================
+//             Quad Vertex
+//==============================================================================
+
+// Builds one corner of the quad that covers the given bounds. Six vertices make up the two triangles.
+fn quad_vertex(vertex_index : u32, instance_index : u32, bound : Bound, padding : f32) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, 1.0),
+    );
+    
+    var position = bound.center + corners[vertex_index] * (bound.size + padding);
+    
+    var out : VertexOutput;
+    out.position = vec4<f32>(position * scale_factor / (resolution * 0.5), 0.0, 1.0);
+    out.point = position * vec2<f32>(1.0, -1.0);
+    out.instance = instance_index;
+    return out;
+}
+
+//==============================================================================
+//             Compositing
+//==============================================================================
+
+// Everything the shader outputs is premultiplied by alpha, which lets overlapping
+// colors be composited with a single "over" operation.
+fn premultiply(color : vec4<f32>) -> vec4<f32> {
+    return vec4<f32>(color.rgb * color.a, color.a);
+}
+
+fn over(top : vec4<f32>, bottom : vec4<f32>) -> vec4<f32> {
+    return top + bottom * (1.0 - top.a);
+}
+
+// Converts a signed distance in logical pixels into how much of the pixel is covered by the shape.
+// The distance is scaled to screen pixels, so a one pixel ramp is centered on the edge.
+fn coverage(distance : f32) -> f32 {
+    return clamp(0.5 - distance * scale_factor, 0.0, 1.0);
+}
+
+//==============================================================================
+//             Rect
+//==============================================================================
+
+// A rounded box in the same space as VertexOutput.point.
+struct RoundedBox {
+    center : vec2<f32>,
+    half_size : vec2<f32>,
+    // top left, bottom left, top right, bottom right
+    radius : vec4<f32>,
+}
+
+const BORDER_INSIDE : u32 = 0u;
+const BORDER_OUTSIDE : u32 = 2u;
+
+const FILL_LINEAR_GRADIENT : u32 = 1u;
+const FILL_RADIAL_GRADIENT : u32 = 2u;
+const FILL_CONIC_GRADIENT : u32 = 3u;
+const FILL_NINE_SLICE : u32 = 4u;
+
+const SLICE_TILE : u32 = 1u;
+
+const PI : f32 = 3.14159265358979;
+
+const BORDER_SOLID : u32 = 0u;
+const BORDER_DASHED : u32 = 1u;
+const BORDER_DOTTED : u32 = 2u;
+
+@vertex
+fn rect_vertex(
+    @builtin(vertex_index) vertex_index : u32,
+    @builtin(instance_index) instance_index : u32,
+) -> VertexOutput {
+    var curr_rect = rect[instance_index];
+    var widths = curr_rect.border_style.border_width;
+    var padding = max(max(widths.x, widths.y), max(widths.z, widths.w));
+    
+    // Make room for the drop shadow, which reaches three standard deviations past its box.
+    var shadow = curr_rect.shadow_style;
+    if (shadow.shadow_color.a > 0.0) {
+        var offset = abs(shadow.shadow_offset);
+        padding = max(padding, shadow.shadow_spread + shadow.shadow_blur * 1.5 + max(offset.x, offset.y));
+    }
+    padding += QUAD_PADDING;
+    return quad_vertex(vertex_index, instance_index, curr_rect.bound, padding);
+}
 
 @fragment
-fn fragment( 
-    @builtin(position) pos: vec4<f32>,
-    @location(0) uv: vec2<f32>,
-) -> @location(0) vec4<f32> {
-    var normalized_uv = uv - 0.5;
-    var point = resolution * uv;
-    point = point - (resolution * 0.5);
-    
-    var current_z = 0.0;
-    var final_color = vec4<f32>(0.0, 0.0, 0.0, 0.0);
-    
-    for (var i = 0; i < i32(arrayLength(&rect)); i++) {
-        var curr_rect = rect[i];
-        var distance = rounded_box_sdf(point, curr_rect);
-        final_color = select (
-            curr_rect.fill_style.fill_color,
-            final_color,
-            distance > 0.0
-        );
-        final_color = select (
-            final_color,
-            curr_rect.border_style.border_color,
-            abs(distance) < curr_rect.border_style.border_width / 2.0
-        );
+fn rect_fragment(in : VertexOutput) -> @location(0) vec4<f32> {
+    var curr_rect = rect[in.instance];
+    var style = curr_rect.border_style;
+    var base = rect_box(curr_rect);
+    
+    var outer = base;
+    var inner = base;
+    switch style.border_alignment {
+        case BORDER_INSIDE: {
+            inner = inset_box(base, style.border_width);
+        }
+        case BORDER_OUTSIDE: {
+            outer = expand_box(base, style.border_width);
+        }
+        default: {
+            outer = expand_box(base, style.border_width * 0.5);
+            inner = inset_box(base, style.border_width * 0.5);
+        }
     }
-        
-    return final_color;
+    
+    var distance = rounded_box_sdf(in.point, base);
+    var fill_coverage = coverage(distance);
+    var fill = fill_color(in.point, base, curr_rect.fill_style);
+    if (curr_rect.fill_style.fill_type == FILL_NINE_SLICE) {
+        fill = nine_slice_color(in.point, base, curr_rect.nine_slice);
+    }
+    fill *= fill_coverage;
+    
+    // Like css, the drop shadow is only visible outside the rect, and the inner shadow only inside.
+    var shadow = shadow_color(in.point, base, curr_rect.shadow_style, false) * (1.0 - fill_coverage);
+    var inner_shadow = shadow_color(in.point, base, curr_rect.inner_shadow_style, true) * fill_coverage;
+    
+    var outer_distance = rounded_box_sdf(in.point, outer);
+    var inner_distance = rounded_box_sdf(in.point, inner);
+    var border = premultiply(style.border_color) * border_coverage(in.point, outer, outer_distance, inner_distance, style);
+    
+    return over(border, over(inner_shadow, over(fill, shadow))) * clip_coverage(in.point, curr_rect.clip);
+}
+
+//==============================================================================
+//             Shadows
+//==============================================================================
+
+// The premultiplied color of a shadow cast by the box. The shadow box is the rect grown by the
+// spread and moved by the offset. An inner shadow is cast by everything outside of the shadow box.
+fn shadow_color(point : vec2<f32>, b : RoundedBox, shadow : ShadowStyle, inner : bool) -> vec4<f32> {
+    if (shadow.shadow_color.a <= 0.0) {
+        return vec4<f32>(0.0);
+    }
+    
+    var spread = select(shadow.shadow_spread, -shadow.shadow_spread, inner);
+    var shadow_box = b;
+    shadow_box.center = b.center + shadow.shadow_offset * vec2<f32>(1.0, -1.0);
+    shadow_box.half_size = max(b.half_size + spread, vec2<f32>(0.0));
+    shadow_box.radius = max(b.radius + spread, vec4<f32>(0.0));
+    
+    var alpha = blurred_box_alpha(point, shadow_box, shadow.shadow_blur * 0.5);
+    if (inner) {
+        alpha = 1.0 - alpha;
+    }
+    return premultiply(shadow.shadow_color) * alpha;
+}
+
+// How much of a rounded box covers a point after a gaussian blur with the given standard
+// deviation. The blur is separable along x, and the remaining y integral is sampled a few times.
+// This is the same closed form approximation that Evan Wallace describes and GPUI uses.
+fn blurred_box_alpha(point : vec2<f32>, b : RoundedBox, sigma : f32) -> f32 {
+    if (sigma < 0.25) {
+        return coverage(rounded_box_sdf(point, b));
+    }
+    
+    var p = point - b.center;
+    var r = select(b.radius.xy, b.radius.zw, p.x > 0.0);
+    r = select(r, r.yy, p.y > 0.0);
+    var corner = min(r.x, min(b.half_size.x, b.half_size.y));
+    
+    var low = p.y - b.half_size.y;
+    var high = p.y + b.half_size.y;
+    var start = clamp(-3.0 * sigma, low, high);
+    var end = clamp(3.0 * sigma, low, high);
+    
+    var step = (end - start) / 4.0;
+    var y = start + step * 0.5;
+    var alpha = 0.0;
+    for (var i = 0; i < 4; i++) {
+        alpha += blur_along_x(p.x, p.y - y, sigma, corner, b.half_size) * gaussian(y, sigma) * step;
+        y += step;
+    }
+    return alpha;
+}
+
+fn blur_along_x(x : f32, y : f32, sigma : f32, corner : f32, half_size : vec2<f32>) -> f32 {
+    var delta = min(half_size.y - corner - abs(y), 0.0);
+    var curved = half_size.x - corner + sqrt(max(0.0, corner * corner - delta * delta));
+    var integral = 0.5 + 0.5 * erf((x + vec2<f32>(-curved, curved)) * (sqrt(0.5) / sigma));
+    return integral.y - integral.x;
+}
+
+fn gaussian(x : f32, sigma : f32) -> f32 {
+    return exp(-(x * x) / (2.0 * sigma * sigma)) / (sqrt(2.0 * PI) * sigma);
+}
+
+fn erf(v : vec2<f32>) -> vec2<f32> {
+    var s = sign(v);
+    var a = abs(v);
+    var r = 1.0 + (0.278393 + (0.230389 + 0.078108 * (a * a)) * a) * a;
+    r = r * r;
+    return s - s / (r * r);
+}
+
+// The premultiplied color of the fill at a point. Gradients are evaluated in the space of
+// the box with y pointing up, so the angles match the rest of Bevy.
+fn fill_color(point : vec2<f32>, b : RoundedBox, fill : FillStyle) -> vec4<f32> {
+    var p = (point - b.center) * vec2<f32>(1.0, -1.0);
+    var center = (fill.gradient_center * 2.0 - 1.0) * b.half_size;
+    
+    switch fill.fill_type {
+        case FILL_LINEAR_GRADIENT: {
+            var direction = vec2<f32>(cos(fill.gradient_angle), sin(fill.gradient_angle));
+            // Half the length of the box along the direction, so the gradient reaches both corners.
+            var extent = max(dot(b.half_size, abs(direction)), 0.0001);
+            return gradient_color(fill, dot(p, direction) / extent * 0.5 + 0.5);
+        }
+        case FILL_RADIAL_GRADIENT: {
+            var farthest_corner = b.half_size + abs(center);
+            var radius = max(length(farthest_corner), 0.0001);
+            return gradient_color(fill, length(p - center) / radius);
+        }
+        case FILL_CONIC_GRADIENT: {
+            var offset = p - center;
+            var angle = atan2(offset.y, offset.x) - fill.gradient_angle;
+            return gradient_color(fill, fract(angle / (2.0 * PI)));
+        }
+        default: {
+            return premultiply(fill.fill_color);
+        }
+    }
+}
+
+// Interpolates the gradient stops at t. Colors are blended premultiplied so transparent
+// stops do not darken their neighbors.
+fn gradient_color(fill : FillStyle, t : f32) -> vec4<f32> {
+    if (fill.stop_count == 0u) {
+        return premultiply(fill.fill_color);
+    }
+    
+    // Arrays can only be indexed dynamically from a variable.
+    var colors = fill.stop_colors;
+    var offsets = fill.stop_offsets;
+    
+    var color = premultiply(colors[0]);
+    for (var i = 1u; i < min(fill.stop_count, 4u); i++) {
+        var start = offsets[i - 1u];
+        var end = offsets[i];
+        var blend = clamp((t - start) / max(end - start, 0.0001), 0.0, 1.0);
+        color = mix(color, premultiply(colors[i]), blend);
+    }
+    return color;
+}
+
+// Maps the point to a pixel of the nine-slice image. The corners keep their size, while the edges
+// and center stretch or tile to fill the rest of the box.
+fn nine_slice_color(point : vec2<f32>, b : RoundedBox, nine_slice : NineSlice) -> vec4<f32> {
+    // From the top left corner of the box, with y pointing down like the image.
+    var p = point - (b.center - b.half_size);
+    var size = b.half_size * 2.0;
+    var border = nine_slice.slice * nine_slice.scale;
+    
+    var middle_x = p.x > border.w && p.x < size.x - border.y;
+    var middle_y = p.y > border.x && p.y < size.y - border.z;
+    var x_mode = select(nine_slice.edge_mode, nine_slice.center_mode, middle_y);
+    var y_mode = select(nine_slice.edge_mode, nine_slice.center_mode, middle_x);
+    
+    var pixel = vec2<f32>(
+        nine_slice_axis(p.x, size.x, border.w, border.y, nine_slice.slice.w, nine_slice.slice.y, nine_slice.image_size.x, nine_slice.scale, x_mode),
+        nine_slice_axis(p.y, size.y, border.x, border.z, nine_slice.slice.x, nine_slice.slice.z, nine_slice.image_size.y, nine_slice.scale, y_mode),
+    );
+    var uv = mix(nine_slice.uv_min, nine_slice.uv_max, pixel / nine_slice.image_size);
+    
+    // The atlas is already premultiplied.
+    return textureSampleLevel(image_atlas, image_sampler, uv, nine_slice.layer, 0.0);
+}
+
+// Maps a position along one axis of the box to a position along the same axis of the image.
+fn nine_slice_axis(
+    p : f32,
+    size : f32,
+    start : f32,
+    end : f32,
+    slice_start : f32,
+    slice_end : f32,
+    image_size : f32,
+    scale : f32,
+    mode : u32,
+) -> f32 {
+    if (p < start) {
+        return p / scale;
+    }
+    if (p > size - end) {
+        return image_size - (size - p) / scale;
+    }
+    
+    var middle = max(image_size - slice_start - slice_end, 0.0001);
+    var t = p - start;
+    if (mode == SLICE_TILE) {
+        return slice_start + (t / scale) % middle;
+    }
+    return slice_start + t / max(size - start - end, 0.0001) * middle;
+}
+
+fn rect_box(rect : Rect) -> RoundedBox {
+    var out : RoundedBox;
+    out.center = rect.bound.center * vec2<f32>(1.0, -1.0);
+    out.half_size = rect.bound.size;
+    out.radius = rect.border_style.border_radius;
+    return out;
+}
+
+// Grows each side of the box outwards. The widths are top, right, bottom, left. Rounded
+// corners grow with the box so the border keeps an even thickness, square corners stay square.
+fn expand_box(b : RoundedBox, widths : vec4<f32>) -> RoundedBox {
+    var out : RoundedBox;
+    out.center = b.center + vec2<f32>(widths.y - widths.w, widths.z - widths.x) * 0.5;
+    out.half_size = b.half_size + vec2<f32>(widths.y + widths.w, widths.x + widths.z) * 0.5;
+    var growth = vec4<f32>(
+        max(widths.x, widths.w),
+        max(widths.z, widths.w),
+        max(widths.x, widths.y),
+        max(widths.z, widths.y),
+    );
+    out.radius = select(vec4<f32>(0.0), b.radius + growth, b.radius > vec4<f32>(0.0));
+    return out;
+}
+
+// Shrinks each side of the box inwards. The widths are top, right, bottom, left.
+fn inset_box(b : RoundedBox, widths : vec4<f32>) -> RoundedBox {
+    var out : RoundedBox;
+    out.center = b.center - vec2<f32>(widths.y - widths.w, widths.z - widths.x) * 0.5;
+    out.half_size = max(b.half_size - vec2<f32>(widths.y + widths.w, widths.x + widths.z) * 0.5, vec2<f32>(0.0));
+    var shrink = vec4<f32>(
+        max(widths.x, widths.w),
+        max(widths.z, widths.w),
+        max(widths.x, widths.y),
+        max(widths.z, widths.y),
+    );
+    out.radius = max(b.radius - shrink, vec4<f32>(0.0));
+    return out;
 }
 
 fn box_sdf(p : vec2<f32>, bounds : vec2<f32>) -> f32 {
@@ -62,12 +533,477 @@ fn box_sdf(p : vec2<f32>, bounds : vec2<f32>) -> f32 {
     return length(max(d, vec2<f32>(0.0, 0.0))) + min(max(d.x,d.y),0.0);
 }
 
-fn rounded_box_sdf(point : vec2<f32>, rect : Rect) -> f32 {
-    var size = (rect.bound.size * 1.0);
-    var p = point - (rect.bound.center * vec2<f32>(1.0, -1.0));
-    var r = select(rect.border_style.border_radius.xy, rect.border_style.border_radius.zw, p.x > 0.0);
+fn rounded_box_sdf(point : vec2<f32>, b : RoundedBox) -> f32 {
+    var size = b.half_size;
+    var p = point - b.center;
+    var r = select(b.radius.xy, b.radius.zw, p.x > 0.0);
     r = select(r, r.yy, p.y > 0.0);
     r = min(r, size);
     var q = abs(p) - size + r.x;
     return min(max(q.x, q.y), 0.0) + length(max(q, vec2<f32>(0.0, 0.0))) - r.x;
-}
\ No newline at end of file
+}
+
+// How much of the pixel is covered by the border band between the outer and inner box. 
+// The band is broken up into dashes or dots along the perimeter when the border is not solid.
+fn border_coverage(
+    point : vec2<f32>,
+    outer : RoundedBox,
+    outer_distance : f32,
+    inner_distance : f32,
+    style : BorderStyle,
+) -> f32 {
+    // Measuring the band as the difference of the two shapes keeps sides without a border empty.
+    var band_coverage = clamp(coverage(outer_distance) - coverage(inner_distance), 0.0, 1.0);
+    if (style.border_pattern == BORDER_SOLID) {
+        return band_coverage;
+    }
+    
+    var along = perimeter_position(point, outer);
+    
+    if (style.border_pattern == BORDER_DASHED) {
+        var period = max(style.dash_length + style.dash_gap, 0.0001);
+        var local = along - period * floor(along / period);
+        var dash_distance = max(-local, local - style.dash_length);
+        return band_coverage * coverage(dash_distance);
+    }
+    
+    // The distances to the two edges of the band add up to the width of the band.
+    var half_width = max((inner_distance - outer_distance) * 0.5, 0.0);
+    var across = (outer_distance + inner_distance) * 0.5;
+    var period = max(half_width * 2.0 + style.dash_gap, 0.0001);
+    var local = along - period * floor(along / period) - period * 0.5;
+    var dot_distance = length(vec2<f32>(local, across)) - half_width;
+    return min(band_coverage, coverage(dot_distance));
+}
+
+// How far along the perimeter of the box a point is, starting at the top left corner and
+// going clockwise. The point is projected on to the closest side.
+fn perimeter_position(point : vec2<f32>, b : RoundedBox) -> f32 {
+    var p = point - b.center;
+    var h = b.half_size;
+    
+    var top = p.y + h.y;
+    var right = h.x - p.x;
+    var bottom = h.y - p.y;
+    var left = p.x + h.x;
+    var closest = min(min(top, right), min(bottom, left));
+    
+    if (closest == top) {
+        return p.x + h.x;
+    } else if (closest == right) {
+        return 2.0 * h.x + p.y + h.y;
+    } else if (closest == bottom) {
+        return 2.0 * h.x + 2.0 * h.y + h.x - p.x;
+    }
+    return 4.0 * h.x + 2.0 * h.y + h.y - p.y;
+}
+
+//==============================================================================
+//             Glyph
+//==============================================================================
+
+@vertex
+fn glyph_vertex(
+    @builtin(vertex_index) vertex_index : u32,
+    @builtin(instance_index) instance_index : u32,
+) -> VertexOutput {
+    return quad_vertex(vertex_index, instance_index, glyphs[instance_index].bound, 0.0);
+}
+
+@fragment
+fn glyph_fragment(in : VertexOutput) -> @location(0) vec4<f32> {
+    var glyph = glyphs[in.instance];
+    return premultiply(glyph.color) * glyph_coverage(in.point, glyph) * clip_coverage(in.point, glyph.clip);
+}
+
+fn glyph_coverage(point : vec2<f32>, glyph : Glyph) -> f32 {
+    var p = point - (glyph.bound.center * vec2<f32>(1.0, -1.0));
+    var local = clamp(p / glyph.bound.size, vec2<f32>(-1.0), vec2<f32>(1.0));
+    if (glyph.px_range <= 0.0) {
+        return 0.0;
+    }
+    
+    var uv = glyph.uv_min + (local * 0.5 + 0.5) * glyph.uv_size;
+    var msdf = sample_font_atlas(uv, glyph.layer);
+    var distance = (median(msdf.r, msdf.g, msdf.b) - 0.5) * glyph.px_range;
+    return coverage(-distance);
+}
+
+// The atlas is stored as 32 bit floats, which can not be filtered by the sampler on
+// every device, so the bilinear filtering is done by hand.
+fn sample_font_atlas(uv : vec2<f32>, layer : u32) -> vec4<f32> {
+    var dims = vec2<f32>(textureDimensions(font_atlas));
+    var texel = uv * dims - 0.5;
+    var base = floor(texel);
+    var f = texel - base;
+    var max_texel = vec2<i32>(dims) - 1;
+    var t00 = clamp(vec2<i32>(base), vec2<i32>(0), max_texel);
+    var t11 = clamp(vec2<i32>(base) + 1, vec2<i32>(0), max_texel);
+    var s00 = textureLoad(font_atlas, t00, layer, 0);
+    var s10 = textureLoad(font_atlas, vec2<i32>(t11.x, t00.y), layer, 0);
+    var s01 = textureLoad(font_atlas, vec2<i32>(t00.x, t11.y), layer, 0);
+    var s11 = textureLoad(font_atlas, t11, layer, 0);
+    return mix(mix(s00, s10, f.x), mix(s01, s11, f.x), f.y);
+}
+
+fn median(r : f32, g : f32, b : f32) -> f32 {
+    return max(min(r, g), min(max(r, g), b));
+}
+
+//==============================================================================
+//             Image
+//==============================================================================
+
+@vertex
+fn image_vertex(
+    @builtin(vertex_index) vertex_index : u32,
+    @builtin(instance_index) instance_index : u32,
+) -> VertexOutput {
+    return quad_vertex(vertex_index, instance_index, image_quads[instance_index].bound, QUAD_PADDING);
+}
+
+@fragment
+fn image_fragment(in : VertexOutput) -> @location(0) vec4<f32> {
+    var image = image_quads[in.instance];
+    var b : RoundedBox;
+    b.center = image.bound.center * vec2<f32>(1.0, -1.0);
+    b.half_size = image.bound.size;
+    b.radius = image.border_radius;
+
+    var local = clamp((in.point - b.center) / b.half_size, vec2<f32>(-1.0), vec2<f32>(1.0));
+    var uv = mix(image.uv_min, image.uv_max, local * 0.5 + 0.5);
+
+    // The atlas is already premultiplied, so only the tint needs to be.
+    var color = textureSample(image_atlas, image_sampler, uv, image.layer) * premultiply(image.tint);
+    return color * coverage(rounded_box_sdf(in.point, b)) * clip_coverage(in.point, image.clip);
+}
+
+//==============================================================================
+//             Layer
+//==============================================================================
+
+// Layers cover the whole target, so children that overflow the layer's bounds are still drawn.
+@vertex
+fn layer_vertex(
+    @builtin(vertex_index) vertex_index : u32,
+    @builtin(instance_index) instance_index : u32,
+) -> VertexOutput {
+    var bound = layers[instance_index].bound;
+    bound.center = vec2<f32>(0.0);
+    bound.size = resolution * 0.5 / scale_factor;
+    return quad_vertex(vertex_index, instance_index, bound, 0.0);
+}
+
+@fragment
+fn layer_fragment(in : VertexOutput) -> @location(0) vec4<f32> {
+    var layer = layers[in.instance];
+    // The layer texture is already premultiplied.
+    var color = textureLoad(layer_texture, vec2<i32>(in.position.xy), 0);
+    return color * layer.opacity * clip_coverage(in.point, layer.clip);
+}
+
+//==============================================================================
+//             Path
+//==============================================================================
+
+const SEGMENT_CURVE : u32 = 0u;
+const SEGMENT_CLOSE : u32 = 1u;
+const SEGMENT_JOIN : u32 = 2u;
+const SEGMENT_ROUND_JOIN : u32 = 3u;
+
+const CAP_BUTT : u32 = 0u;
+const CAP_SQUARE : u32 = 1u;
+
+const FILL_RULE_EVEN_ODD : u32 = 1u;
+
+@vertex
+fn path_vertex(
+    @builtin(vertex_index) vertex_index : u32,
+    @builtin(instance_index) instance_index : u32,
+) -> VertexOutput {
+    return quad_vertex(vertex_index, instance_index, paths[instance_index].bound, QUAD_PADDING);
+}
+
+@fragment
+fn path_fragment(in : VertexOutput) -> @location(0) vec4<f32> {
+    var path = paths[in.instance];
+    // The segments are stored with y pointing up, like the bounds.
+    var p = in.point * vec2<f32>(1.0, -1.0);
+    var half_width = path.stroke_width * 0.5;
+    
+    var fill_distance = 1e10;
+    var stroke_distance = 1e10;
+    var winding = 0;
+    
+    for (var i = 0u; i < path.segment_count; i++) {
+        var segment = path_segments[path.segment_start + i];
+        switch segment.kind {
+            case SEGMENT_JOIN: {
+                stroke_distance = min(stroke_distance, join_distance(p, segment));
+            }
+            case SEGMENT_ROUND_JOIN: {
+                stroke_distance = min(stroke_distance, length(p - segment.start) - half_width);
+            }
+            default: {
+                var closest = bezier_distance(p, segment.start, segment.control, segment.end);
+                fill_distance = min(fill_distance, closest.x);
+                winding += bezier_winding(p, segment.start, segment.control, segment.end);
+                if (segment.kind == SEGMENT_CURVE) {
+                    stroke_distance = min(stroke_distance, stroke_segment_distance(p, segment, closest, half_width));
+                }
+            }
+        }
+    }
+    
+    var inside = winding != 0;
+    if (path.fill_rule == FILL_RULE_EVEN_ODD) {
+        inside = (winding & 1) != 0;
+    }
+    
+    var fill = premultiply(path.fill_color) * coverage(select(fill_distance, -fill_distance, inside));
+    var stroke = premultiply(path.stroke_color) * coverage(stroke_distance) * step(0.0001, half_width);
+    return over(stroke, fill) * clip_coverage(in.point, path.clip);
+}
+
+// The signed distance to the stroke of a single curve. The closest point on the curve decides
+// which part of the stroke the point is next to, and the caps only apply past the ends.
+fn stroke_segment_distance(p : vec2<f32>, segment : PathSegment, closest : vec2<f32>, half_width : f32) -> f32 {
+    var distance = closest.x - half_width;
+    
+    if (closest.y <= 0.0) {
+        var direction = segment_direction(segment.start, segment.control, segment.end);
+        return cap_distance(p - segment.start, -direction, half_width, segment.start_cap, distance);
+    }
+    if (closest.y >= 1.0) {
+        var direction = segment_direction(segment.end, segment.control, segment.start);
+        return cap_distance(p - segment.end, -direction, half_width, segment.end_cap, distance);
+    }
+    return distance;
+}
+
+// The direction the curve leaves `end_point`, which falls back to the far end when the control point is on top of it.
+fn segment_direction(end_point : vec2<f32>, control : vec2<f32>, far_point : vec2<f32>) -> vec2<f32> {
+    var direction = control - end_point;
+    if (dot(direction, direction) < 1e-8) {
+        direction = far_point - end_point;
+    }
+    return normalize(direction);
+}
+
+// `outward` points away from the curve, past its end.
+fn cap_distance(local : vec2<f32>, outward : vec2<f32>, half_width : f32, cap : u32, distance : f32) -> f32 {
+    var along = dot(local, outward);
+    if (along <= 0.0) {
+        return distance;
+    }
+    
+    var across = abs(dot(local, vec2<f32>(-outward.y, outward.x))) - half_width;
+    switch cap {
+        case CAP_BUTT: {
+            return max(across, along);
+        }
+        case CAP_SQUARE: {
+            return max(across, along - half_width);
+        }
+        default: {
+            return distance;
+        }
+    }
+}
+
+// The signed distance to the four sided shape of a miter or bevel join.
+fn join_distance(p : vec2<f32>, segment : PathSegment) -> f32 {
+    var points = array<vec2<f32>, 4>(segment.start, segment.control, segment.end, segment.extra);
+    var distance = dot(p - points[0], p - points[0]);
+    var side = 1.0;
+    
+    for (var i = 0u; i < 4u; i++) {
+        var j = (i + 3u) % 4u;
+        var edge = points[j] - points[i];
+        var w = p - points[i];
+        var b = w - edge * clamp(dot(w, edge) / max(dot(edge, edge), 1e-8), 0.0, 1.0);
+        distance = min(distance, dot(b, b));
+        
+        var crossing = vec3<bool>(p.y >= points[i].y, p.y < points[j].y, edge.x * w.y > edge.y * w.x);
+        if (all(crossing) || !any(crossing)) {
+            side = -side;
+        }
+    }
+    return side * sqrt(distance);
+}
+
+// The distance to a quadratic bezier curve, and how far along the curve the closest point is.
+fn bezier_distance(p : vec2<f32>, p0 : vec2<f32>, p1 : vec2<f32>, p2 : vec2<f32>) -> vec2<f32> {
+    var a = p1 - p0;
+    var b = p0 - 2.0 * p1 + p2;
+    
+    // Lines are stored with the control point halfway along, which the cubic solve can not handle.
+    if (dot(b, b) < 1e-6) {
+        var line = p2 - p0;
+        var t = clamp(dot(p - p0, line) / max(dot(line, line), 1e-8), 0.0, 1.0);
+        return vec2<f32>(length(p - (p0 + line * t)), t);
+    }
+    
+    var c = a * 2.0;
+    var d = p0 - p;
+    var kk = 1.0 / dot(b, b);
+    var kx = kk * dot(a, b);
+    var ky = kk * (2.0 * dot(a, a) + dot(d, b)) / 3.0;
+    var kz = kk * dot(d, a);
+    var q_p = ky - kx * kx;
+    var q_p3 = q_p * q_p * q_p;
+    var q = kx * (2.0 * kx * kx - 3.0 * ky) + kz;
+    var h = q * q + 4.0 * q_p3;
+    
+    if (h >= 0.0) {
+        h = sqrt(h);
+        var x = (vec2<f32>(h, -h) - q) / 2.0;
+        var uv = sign(x) * pow(abs(x), vec2<f32>(1.0 / 3.0));
+        var t = clamp(uv.x + uv.y - kx, 0.0, 1.0);
+        return vec2<f32>(length(d + (c + b * t) * t), t);
+    }
+    
+    var z = sqrt(-q_p);
+    var v = acos(q / (q_p * z * 2.0)) / 3.0;
+    var m = cos(v);
+    var n = sin(v) * 1.732050808;
+    var t = clamp(vec2<f32>(m + m, -n - m) * z - kx, vec2<f32>(0.0), vec2<f32>(1.0));
+    var d0 = length(d + (c + b * t.x) * t.x);
+    var d1 = length(d + (c + b * t.y) * t.y);
+    return select(vec2<f32>(d1, t.y), vec2<f32>(d0, t.x), d0 < d1);
+}
+
+// How many times the curve winds around the point, counted where it crosses the line going
+// right from the point. Crossings going up count as 1, and going down as -1.
+fn bezier_winding(p : vec2<f32>, p0 : vec2<f32>, p1 : vec2<f32>, p2 : vec2<f32>) -> i32 {
+    var a = p0.y - 2.0 * p1.y + p2.y;
+    var b = 2.0 * (p1.y - p0.y);
+    var c = p0.y - p.y;
+    
+    var roots = vec2<f32>(-1.0);
+    if (abs(a) < 1e-6) {
+        if (abs(b) > 1e-6) {
+            roots.x = -c / b;
+        }
+    } else {
+        var discriminant = b * b - 4.0 * a * c;
+        if (discriminant >= 0.0) {
+            var root = sqrt(discriminant);
+            roots = vec2<f32>(-b - root, -b + root) / (2.0 * a);
+        }
+    }
+    
+    var winding = 0;
+    for (var i = 0; i < 2; i++) {
+        var t = roots[i];
+        // Half open, so a crossing right on the point between two curves is only counted once.
+        if (t < 0.0 || t >= 1.0) {
+            continue;
+        }
+        
+        var x = mix(mix(p0.x, p1.x, t), mix(p1.x, p2.x, t), t);
+        var slope = 2.0 * a * t + b;
+        if (x > p.x && slope != 0.0) {
+            winding += select(-1, 1, slope > 0.0);
+        }
+    }
+    return winding;
+}
+
+//==============================================================================
+//             Ellipse
+//==============================================================================
+
+@vertex
+fn ellipse_vertex(
+    @builtin(vertex_index) vertex_index : u32,
+    @builtin(instance_index) instance_index : u32,
+) -> VertexOutput {
+    var ellipse = ellipses[instance_index];
+    return quad_vertex(vertex_index, instance_index, ellipse.bound, QUAD_PADDING + ellipse.stroke_width * 0.5);
+}
+
+@fragment
+fn ellipse_fragment(in : VertexOutput) -> @location(0) vec4<f32> {
+    var ellipse = ellipses[in.instance];
+    // Angles go counter clockwise, so y points up.
+    var p = (in.point - ellipse.bound.center * vec2<f32>(1.0, -1.0)) * vec2<f32>(1.0, -1.0);
+    
+    var distance = ellipse_sdf(p, ellipse.bound.size);
+    if (ellipse.inner_radius > 0.0) {
+        var thickness = min(ellipse.bound.size.x, ellipse.bound.size.y) - ellipse.inner_radius;
+        distance = max(distance, -(distance + thickness));
+    }
+    if (ellipse.end_angle - ellipse.start_angle < 2.0 * PI) {
+        distance = max(distance, sector_sdf(p, ellipse.start_angle, ellipse.end_angle));
+    }
+    
+    var fill = premultiply(ellipse.fill_color) * coverage(distance);
+    var stroke_distance = abs(distance) - ellipse.stroke_width * 0.5;
+    var stroke = premultiply(ellipse.stroke_color) * coverage(stroke_distance) * step(0.0001, ellipse.stroke_width);
+    return over(stroke, fill) * clip_coverage(in.point, ellipse.clip);
+}
+
+// The exact signed distance to an ellipse with the given radii, by Inigo Quilez.
+fn ellipse_sdf(point : vec2<f32>, radii : vec2<f32>) -> f32 {
+    var p = abs(point);
+    var ab = radii;
+    if (p.x > p.y) {
+        p = p.yx;
+        ab = ab.yx;
+    }
+    
+    var l = ab.y * ab.y - ab.x * ab.x;
+    // The solve below divides by the difference of the radii, so circles are handled on their own.
+    if (abs(l) < 1e-3) {
+        return length(p) - ab.x;
+    }
+    
+    var m = ab.x * p.x / l;
+    var m2 = m * m;
+    var n = ab.y * p.y / l;
+    var n2 = n * n;
+    var c = (m2 + n2 - 1.0) / 3.0;
+    var c3 = c * c * c;
+    var q = c3 + m2 * n2 * 2.0;
+    var d = c3 + m2 * n2;
+    var g = m + m * n2;
+    
+    var co : f32;
+    if (d < 0.0) {
+        var h = acos(q / c3) / 3.0;
+        var s = cos(h);
+        var t = sin(h) * sqrt(3.0);
+        var rx = sqrt(-c * (s + t + 2.0) + m2);
+        var ry = sqrt(-c * (s - t + 2.0) + m2);
+        co = (ry + sign(l) * rx + abs(g) / (rx * ry) - m) / 2.0;
+    } else {
+        var h = 2.0 * m * n * sqrt(d);
+        var s = sign(q + h) * pow(abs(q + h), 1.0 / 3.0);
+        var u = sign(q - h) * pow(abs(q - h), 1.0 / 3.0);
+        var rx = -s - u - c * 4.0 + 2.0 * m2;
+        var ry = (s - u) * sqrt(3.0);
+        var rm = sqrt(rx * rx + ry * ry);
+        co = (ry / sqrt(rm - rx) + 2.0 * g / rm - m) / 2.0;
+    }
+    
+    var r = ab * vec2<f32>(co, sqrt(max(1.0 - co * co, 0.0)));
+    return length(r - p) * sign(p.y - r.y);
+}
+
+// The signed distance to the wedge between two angles, going counter clockwise from `start_angle`.
+fn sector_sdf(p : vec2<f32>, start_angle : f32, end_angle : f32) -> f32 {
+    var sweep = end_angle - start_angle;
+    var angle = atan2(p.y, p.x) - start_angle;
+    var inside = angle - floor(angle / (2.0 * PI)) * 2.0 * PI <= sweep;
+    
+    var start_ray = vec2<f32>(cos(start_angle), sin(start_angle));
+    var end_ray = vec2<f32>(cos(end_angle), sin(end_angle));
+    var distance = min(ray_distance(p, start_ray), ray_distance(p, end_ray));
+    return select(distance, -distance, inside);
+}
+
+// The distance to the ray that leaves the origin in `direction`.
+fn ray_distance(p : vec2<f32>, direction : vec2<f32>) -> f32 {
+    return length(p - direction * max(dot(p, direction), 0.0));
+}
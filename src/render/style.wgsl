@@ -3,9 +3,26 @@
 struct BorderStyle {
     border_color : vec4<f32>,
     border_radius : vec4<f32>,
-    border_width : f32,
+    border_width : vec4<f32>,
+    border_alignment : u32,
+    border_pattern : u32,
+    dash_length : f32,
+    dash_gap : f32,
+}
+
+struct ShadowStyle {
+    shadow_color : vec4<f32>,
+    shadow_offset : vec2<f32>,
+    shadow_blur : f32,
+    shadow_spread : f32,
 }
 
 struct FillStyle {
     fill_color : vec4<f32>,
+    fill_type : u32,
+    gradient_center : vec2<f32>,
+    gradient_angle : f32,
+    stop_count : u32,
+    stop_offsets : vec4<f32>,
+    stop_colors : array<vec4<f32>, 4>,
 }
@@ -0,0 +1,1042 @@
+//==============================================================================
+//  Svg icons are loaded into paths, so they stay sharp at any size. Only the
+//  parts of svg that icons use are read: paths and basic shapes with their
+//  fill and stroke, inside of groups and transforms. Gradients, text, masks
+//  and filters are skipped.
+//==============================================================================
+
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+use bevy::{
+    asset::{AssetLoader, AsyncReadExt},
+    math::Affine2,
+    prelude::*,
+    render::{Extract, RenderApp},
+    utils::{BoxedFuture, HashMap},
+};
+use thiserror::Error;
+
+use crate::common::{bounds::Bounds, MarkSaikoUiDirty};
+
+use super::{
+    buffer::{FillRule, IconBuffer, LineCap, LineJoin, PathBuffer, PathSegmentBuffer},
+    path::SaikoPath,
+};
+
+//==============================================================================
+//             SaikoSvgPlugin
+//==============================================================================
+
+pub struct SaikoSvgPlugin;
+
+impl Plugin for SaikoSvgPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_asset_loader::<SaikoSvgLoader>()
+            .init_asset::<SaikoSvg>()
+            .add_systems(Update, mark_ui_dirty_on_svg_load)
+        ;
+
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app
+            .init_resource::<SaikoSvgIcons>()
+            .add_systems(ExtractSchedule, extract_svg_icons)
+        ;
+    }
+}
+
+//==============================================================================
+//             SaikoSvg Systems
+//==============================================================================
+
+/// Copies every svg that has changed into the render world, where icons are laid out.
+fn extract_svg_icons(
+    mut icons : ResMut<SaikoSvgIcons>,
+    mut svg_events : Extract<EventReader<AssetEvent<SaikoSvg>>>,
+    svgs : Extract<Res<Assets<SaikoSvg>>>,
+) {
+    for event in svg_events.read() {
+        match event {
+            AssetEvent::Added { id } | AssetEvent::Modified { id } => {
+                if let Some(svg) = svgs.get(*id) {
+                    icons.icons.insert(*id, svg.clone());
+                }
+            },
+            AssetEvent::Removed { id } => {
+                icons.icons.remove(id);
+            },
+            _ => {},
+        }
+    }
+}
+
+/// Icons that are drawn before they finish loading are skipped, so the ui is redrawn once they are ready.
+fn mark_ui_dirty_on_svg_load(
+    mut svg_events : EventReader<AssetEvent<SaikoSvg>>,
+    mut dirty : EventWriter<MarkSaikoUiDirty>,
+) {
+    let svg_loaded = svg_events
+        .read()
+        .any(|event| matches!(event, AssetEvent::LoadedWithDependencies { .. } | AssetEvent::Modified { .. }));
+
+    if svg_loaded {
+        dirty.send(MarkSaikoUiDirty);
+    }
+}
+
+//==============================================================================
+//             SaikoSvgIcons
+//==============================================================================
+
+/// Every svg that has been loaded, kept in the render world.
+#[derive(Resource, Default)]
+pub(crate) struct SaikoSvgIcons {
+    icons : HashMap<AssetId<SaikoSvg>, SaikoSvg>,
+}
+
+impl SaikoSvgIcons {
+    /// Pushes a path for every shape of the icon, scaled to fit inside of its bounds.
+    pub(crate) fn layout_icon(&self, icon : &IconBuffer, paths : &mut Vec<PathBuffer>, segments : &mut Vec<PathSegmentBuffer>) {
+        let Some(svg) = self.icons.get(&icon.icon) else { return };
+        let scale = (icon.bound.size * 2.0 / svg.size).min_element();
+
+        for shape in &svg.shapes {
+            let mut path = PathBuffer {
+                bound : Bounds::new(icon.bound.center, Vec2::ZERO, icon.bound.z_index),
+                fill_color : shape.fill.map_or(Color::NONE, |paint| paint.resolve(icon.tint)),
+                stroke_color : shape.stroke.map_or(Color::NONE, |paint| paint.resolve(icon.tint)),
+                stroke_width : shape.stroke_width * scale,
+                fill_rule : shape.fill_rule as u32,
+                clip : icon.clip,
+                ..Default::default()
+            };
+
+            let outline = shape.outline.scaled(scale);
+            outline.push_segments(icon.bound.center, &mut path, shape.line_cap, shape.line_join, segments);
+            paths.push(path);
+        }
+    }
+}
+
+//==============================================================================
+//             SaikoSvg
+//==============================================================================
+
+/// An svg that has been turned into paths. The paths are in svg units, relative to the center of
+/// the view box, with y pointing up.
+#[derive(Asset, TypePath, Debug, Clone, Default)]
+pub struct SaikoSvg {
+    /// The size of the view box.
+    size : Vec2,
+    shapes : Vec<SaikoSvgShape>,
+}
+
+#[derive(Debug, Clone)]
+struct SaikoSvgShape {
+    outline : SaikoPath,
+    fill : Option<SaikoSvgPaint>,
+    fill_rule : FillRule,
+    stroke : Option<SaikoSvgPaint>,
+    stroke_width : f32,
+    line_cap : LineCap,
+    line_join : LineJoin,
+}
+
+/// The color a shape is filled or stroked with.
+#[derive(Debug, Clone, Copy)]
+enum SaikoSvgPaint {
+    /// Drawn with the tint of the icon, faded by the opacity.
+    CurrentColor(f32),
+    Color(Color),
+}
+
+impl SaikoSvgPaint {
+    /// Colors set by the svg keep their color, and are only faded by the tint.
+    fn resolve(self, tint : Color) -> Color {
+        match self {
+            SaikoSvgPaint::CurrentColor(opacity) => tint.with_a(tint.a() * opacity),
+            SaikoSvgPaint::Color(color) => color.with_a(color.a() * tint.a()),
+        }
+    }
+
+    fn with_opacity(self, opacity : f32) -> Self {
+        match self {
+            SaikoSvgPaint::CurrentColor(alpha) => SaikoSvgPaint::CurrentColor(alpha * opacity),
+            SaikoSvgPaint::Color(color) => SaikoSvgPaint::Color(color.with_a(color.a() * opacity)),
+        }
+    }
+}
+
+//==============================================================================
+//             SaikoSvg AssetLoader
+//==============================================================================
+
+#[derive(Default)]
+struct SaikoSvgLoader;
+
+impl AssetLoader for SaikoSvgLoader {
+    type Asset = SaikoSvg;
+
+    type Settings = ();
+
+    type Error = SaikoSvgError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut bevy::asset::io::Reader,
+        _settings: &'a Self::Settings,
+        _load_context: &'a mut bevy::asset::LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut data = Vec::new();
+            reader.read_to_end(&mut data).await?;
+
+            parse_svg(std::str::from_utf8(&data)?)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["svg"]
+    }
+}
+
+//==============================================================================
+//             SaikoSvgError
+//==============================================================================
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum SaikoSvgError {
+    /// An [IO](std::io) Error
+    #[error("Could not load asset: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("The svg is not valid utf-8: {0}")]
+    Utf8(#[from] std::str::Utf8Error),
+    #[error("The svg has no viewBox, width or height")]
+    MissingSize,
+}
+
+//==============================================================================
+//             Svg Parsing
+//==============================================================================
+
+/// The elements whose children are never drawn directly.
+const HIDDEN_ELEMENTS : [&str; 10] = [
+    "defs", "clipPath", "mask", "symbol", "pattern", "marker",
+    "linearGradient", "radialGradient", "filter", "style",
+];
+
+/// The styles that children inherit from the groups they are in.
+#[derive(Clone)]
+struct SvgStyle {
+    transform : Affine2,
+    fill : Option<SaikoSvgPaint>,
+    fill_rule : FillRule,
+    fill_opacity : f32,
+    stroke : Option<SaikoSvgPaint>,
+    stroke_width : f32,
+    stroke_opacity : f32,
+    line_cap : LineCap,
+    line_join : LineJoin,
+    opacity : f32,
+    /// The opacity of every group the element is in, multiplied together.
+    group_opacity : f32,
+    hidden : bool,
+}
+
+impl Default for SvgStyle {
+    fn default() -> Self {
+        Self {
+            transform : Affine2::IDENTITY,
+            // Svg fills shapes with black by default. Icons are meant to be tinted, so the tint is
+            // used instead.
+            fill : Some(SaikoSvgPaint::CurrentColor(1.0)),
+            fill_rule : FillRule::NonZero,
+            fill_opacity : 1.0,
+            stroke : None,
+            stroke_width : 1.0,
+            stroke_opacity : 1.0,
+            line_cap : LineCap::Butt,
+            line_join : LineJoin::Miter,
+            opacity : 1.0,
+            group_opacity : 1.0,
+            hidden : false,
+        }
+    }
+}
+
+fn parse_svg(source : &str) -> Result<SaikoSvg, SaikoSvgError> {
+    let mut svg = SaikoSvg::default();
+    let mut styles = vec![SvgStyle::default()];
+    let mut rest = source;
+
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start..];
+
+        // Comments, declarations and processing instructions are skipped.
+        let skip_to = if rest.starts_with("<!--") {
+            Some("-->")
+        } else if rest.starts_with("<!") || rest.starts_with("<?") {
+            Some(">")
+        } else {
+            None
+        };
+        if let Some(end) = skip_to {
+            match rest.find(end) {
+                Some(index) => rest = &rest[index + end.len()..],
+                None => break,
+            }
+            continue;
+        }
+
+        if rest.starts_with("</") {
+            if styles.len() > 1 {
+                styles.pop();
+            }
+            match rest.find('>') {
+                Some(index) => rest = &rest[index + 1..],
+                None => break,
+            }
+            continue;
+        }
+
+        let Some((name, attributes, is_empty, tag_len)) = parse_tag(rest) else { break };
+        rest = &rest[tag_len..];
+
+        let mut style = styles.last().cloned().unwrap_or_default();
+        if name == "svg" && svg.size == Vec2::ZERO {
+            style.transform = view_box_transform(&attributes, &mut svg)?;
+        }
+        apply_attributes(&mut style, &attributes);
+        style.hidden |= HIDDEN_ELEMENTS.contains(&name);
+
+        if !style.hidden {
+            push_shape(&mut svg, name, &attributes, &style);
+        }
+        if !is_empty {
+            styles.push(style);
+        }
+    }
+
+    if svg.size == Vec2::ZERO {
+        return Err(SaikoSvgError::MissingSize);
+    }
+
+    Ok(svg)
+}
+
+/// Reads the name and attributes of the tag at the start of `source`, and whether it closes itself.
+/// Also returns how long the tag is.
+fn parse_tag(source : &str) -> Option<(&str, HashMap<&str, &str>, bool, usize)> {
+    let bytes = source.as_bytes();
+    let name_end = source[1..]
+        .find(|c : char| c.is_whitespace() || c == '/' || c == '>')
+        .map(|index| index + 1)?;
+    let name = &source[1..name_end];
+
+    let mut attributes = HashMap::new();
+    let mut index = name_end;
+    loop {
+        while bytes.get(index)?.is_ascii_whitespace() {
+            index += 1;
+        }
+        match bytes[index] {
+            b'>' => return Some((name, attributes, false, index + 1)),
+            b'/' if bytes.get(index + 1) == Some(&b'>') => return Some((name, attributes, true, index + 2)),
+            _ => {},
+        }
+
+        let key_start = index;
+        while !matches!(bytes.get(index)?, b'=' | b'>' | b'/') && !bytes[index].is_ascii_whitespace() {
+            index += 1;
+        }
+        let key = &source[key_start..index];
+
+        while bytes.get(index)?.is_ascii_whitespace() {
+            index += 1;
+        }
+        if bytes[index] != b'=' {
+            // An attribute without a value, which svg does not use.
+            if index == key_start {
+                index += 1;
+            }
+            continue;
+        }
+        index += 1;
+        while bytes.get(index)?.is_ascii_whitespace() {
+            index += 1;
+        }
+
+        let quote = *bytes.get(index)?;
+        if quote != b'"' && quote != b'\'' {
+            return None;
+        }
+        let value_start = index + 1;
+        let value_end = value_start + source[value_start..].find(quote as char)?;
+        attributes.insert(key, &source[value_start..value_end]);
+        index = value_end + 1;
+    }
+}
+
+/// Sets the size of the svg from its view box, and returns the transform that moves the view box
+/// to be centered on the origin with y pointing up.
+fn view_box_transform(attributes : &HashMap<&str, &str>, svg : &mut SaikoSvg) -> Result<Affine2, SaikoSvgError> {
+    let view_box = attributes
+        .get("viewBox")
+        .map(|value| SvgNumbers::new(value).take(4).collect::<Vec<_>>())
+        .filter(|numbers| numbers.len() == 4);
+
+    let (min, size) = match view_box {
+        Some(numbers) => (Vec2::new(numbers[0], numbers[1]), Vec2::new(numbers[2], numbers[3])),
+        None => {
+            let width = attributes.get("width").and_then(|value| parse_length(value));
+            let height = attributes.get("height").and_then(|value| parse_length(value));
+            (Vec2::ZERO, Vec2::new(width.unwrap_or(0.0), height.unwrap_or(0.0)))
+        },
+    };
+
+    if size.x <= 0.0 || size.y <= 0.0 {
+        return Err(SaikoSvgError::MissingSize);
+    }
+
+    svg.size = size;
+    Ok(Affine2::from_scale(Vec2::new(1.0, -1.0)) * Affine2::from_translation(-(min + size * 0.5)))
+}
+
+/// Applies the presentation attributes of an element, and then its style attribute.
+fn apply_attributes(style : &mut SvgStyle, attributes : &HashMap<&str, &str>) {
+    if let Some(transform) = attributes.get("transform") {
+        style.transform *= parse_transform(transform);
+    }
+
+    // Opacity is not inherited, but the children of a group are faded along with it.
+    style.group_opacity *= style.opacity;
+    style.opacity = 1.0;
+
+    for (name, value) in attributes {
+        apply_property(style, name, value);
+    }
+
+    if let Some(declarations) = attributes.get("style") {
+        for declaration in declarations.split(';') {
+            if let Some((name, value)) = declaration.split_once(':') {
+                apply_property(style, name.trim(), value.trim());
+            }
+        }
+    }
+}
+
+fn apply_property(style : &mut SvgStyle, name : &str, value : &str) {
+    match name {
+        "fill" => if let Some(paint) = parse_paint(value) { style.fill = paint },
+        "stroke" => if let Some(paint) = parse_paint(value) { style.stroke = paint },
+        "stroke-width" => if let Some(width) = parse_length(value) { style.stroke_width = width.max(0.0) },
+        "fill-opacity" => if let Some(opacity) = parse_length(value) { style.fill_opacity = opacity.clamp(0.0, 1.0) },
+        "stroke-opacity" => if let Some(opacity) = parse_length(value) { style.stroke_opacity = opacity.clamp(0.0, 1.0) },
+        "opacity" => if let Some(opacity) = parse_length(value) { style.opacity = opacity.clamp(0.0, 1.0) },
+        "fill-rule" => style.fill_rule = match value {
+            "evenodd" => FillRule::EvenOdd,
+            _ => FillRule::NonZero,
+        },
+        "stroke-linecap" => style.line_cap = match value {
+            "round" => LineCap::Round,
+            "square" => LineCap::Square,
+            _ => LineCap::Butt,
+        },
+        "stroke-linejoin" => style.line_join = match value {
+            "round" => LineJoin::Round,
+            "bevel" => LineJoin::Bevel,
+            _ => LineJoin::Miter,
+        },
+        "display" if value == "none" => style.hidden = true,
+        _ => {},
+    }
+}
+
+/// The paint of a fill or stroke, where `None` is no paint. Returns `None` if the paint can't be read.
+fn parse_paint(value : &str) -> Option<Option<SaikoSvgPaint>> {
+    match value.trim() {
+        "none" | "transparent" => Some(None),
+        "currentColor" | "inherit" => Some(Some(SaikoSvgPaint::CurrentColor(1.0))),
+        // Gradients and patterns are not supported, so they are drawn with the tint.
+        value if value.starts_with("url(") => Some(Some(SaikoSvgPaint::CurrentColor(1.0))),
+        value => parse_color(value).map(|color| Some(SaikoSvgPaint::Color(color))),
+    }
+}
+
+fn parse_color(value : &str) -> Option<Color> {
+    if value.starts_with('#') {
+        return Color::hex(value).ok();
+    }
+
+    if let Some(channels) = value.strip_prefix("rgb(").or(value.strip_prefix("rgba(")) {
+        let channels = channels
+            .trim_end_matches(')')
+            .split(|c : char| c == ',' || c.is_whitespace() || c == '/')
+            .filter(|channel| !channel.is_empty())
+            .collect::<Vec<_>>();
+        // Color channels go up to 255, but the alpha goes up to 1.
+        let channel = |channel : &str, max : f32| match channel.strip_suffix('%') {
+            Some(percent) => percent.parse::<f32>().ok().map(|percent| percent / 100.0),
+            None => channel.parse::<f32>().ok().map(|channel| channel / max),
+        };
+
+        return match channels[..] {
+            [r, g, b] => Some(Color::rgb(channel(r, 255.0)?, channel(g, 255.0)?, channel(b, 255.0)?)),
+            [r, g, b, a] => Some(Color::rgba(channel(r, 255.0)?, channel(g, 255.0)?, channel(b, 255.0)?, channel(a, 1.0)?)),
+            _ => None,
+        };
+    }
+
+    let [r, g, b] = match value {
+        "black" => [0, 0, 0],
+        "white" => [255, 255, 255],
+        "red" => [255, 0, 0],
+        "green" => [0, 128, 0],
+        "lime" => [0, 255, 0],
+        "blue" => [0, 0, 255],
+        "yellow" => [255, 255, 0],
+        "orange" => [255, 165, 0],
+        "gray" | "grey" => [128, 128, 128],
+        _ => return None,
+    };
+    Some(Color::rgb_u8(r, g, b))
+}
+
+/// Reads a length in user units. Units other than pixels are not supported.
+fn parse_length(value : &str) -> Option<f32> {
+    value.trim().trim_end_matches("px").parse().ok()
+}
+
+fn parse_transform(value : &str) -> Affine2 {
+    let mut transform = Affine2::IDENTITY;
+
+    for function in value.split_inclusive(')') {
+        let Some((name, arguments)) = function.split_once('(') else { continue };
+        let arguments = SvgNumbers::new(arguments.trim_end_matches(')')).collect::<Vec<_>>();
+
+        let next = match (name.trim_matches(|c : char| c == ',' || c.is_whitespace()), &arguments[..]) {
+            ("matrix", &[a, b, c, d, e, f]) => Affine2::from_cols_array(&[a, b, c, d, e, f]),
+            ("translate", &[x]) => Affine2::from_translation(Vec2::new(x, 0.0)),
+            ("translate", &[x, y]) => Affine2::from_translation(Vec2::new(x, y)),
+            ("scale", &[x]) => Affine2::from_scale(Vec2::splat(x)),
+            ("scale", &[x, y]) => Affine2::from_scale(Vec2::new(x, y)),
+            ("rotate", &[angle]) => Affine2::from_angle(angle.to_radians()),
+            ("rotate", &[angle, x, y]) => {
+                let center = Vec2::new(x, y);
+                Affine2::from_translation(center) * Affine2::from_angle(angle.to_radians()) * Affine2::from_translation(-center)
+            },
+            ("skewX", &[angle]) => Affine2::from_cols_array(&[1.0, 0.0, angle.to_radians().tan(), 1.0, 0.0, 0.0]),
+            ("skewY", &[angle]) => Affine2::from_cols_array(&[1.0, angle.to_radians().tan(), 0.0, 1.0, 0.0, 0.0]),
+            _ => continue,
+        };
+        transform *= next;
+    }
+
+    transform
+}
+
+/// Builds the outline of the element, if it is a shape, and adds it to the svg.
+fn push_shape(svg : &mut SaikoSvg, name : &str, attributes : &HashMap<&str, &str>, style : &SvgStyle) {
+    let number = |key : &str| attributes.get(key).and_then(|value| parse_length(value));
+    let mut builder = SvgPathBuilder::new(style.transform);
+
+    match name {
+        "path" => builder.path_data(attributes.get("d").copied().unwrap_or_default()),
+        "rect" => {
+            let position = Vec2::new(number("x").unwrap_or(0.0), number("y").unwrap_or(0.0));
+            let size = Vec2::new(number("width").unwrap_or(0.0), number("height").unwrap_or(0.0));
+            let radius = match (number("rx"), number("ry")) {
+                (Some(rx), Some(ry)) => Vec2::new(rx, ry),
+                (Some(radius), None) | (None, Some(radius)) => Vec2::splat(radius),
+                (None, None) => Vec2::ZERO,
+            };
+            builder.rect(position, size, radius.min(size * 0.5).max(Vec2::ZERO));
+        },
+        "circle" => {
+            let center = Vec2::new(number("cx").unwrap_or(0.0), number("cy").unwrap_or(0.0));
+            builder.ellipse(center, Vec2::splat(number("r").unwrap_or(0.0)));
+        },
+        "ellipse" => {
+            let center = Vec2::new(number("cx").unwrap_or(0.0), number("cy").unwrap_or(0.0));
+            builder.ellipse(center, Vec2::new(number("rx").unwrap_or(0.0), number("ry").unwrap_or(0.0)));
+        },
+        "line" => {
+            builder.move_to(Vec2::new(number("x1").unwrap_or(0.0), number("y1").unwrap_or(0.0)));
+            builder.line_to(Vec2::new(number("x2").unwrap_or(0.0), number("y2").unwrap_or(0.0)));
+        },
+        "polyline" | "polygon" => {
+            let numbers = SvgNumbers::new(attributes.get("points").copied().unwrap_or_default()).collect::<Vec<_>>();
+            for (index, point) in numbers.chunks_exact(2).enumerate() {
+                match index {
+                    0 => builder.move_to(Vec2::new(point[0], point[1])),
+                    _ => builder.line_to(Vec2::new(point[0], point[1])),
+                }
+            }
+            if name == "polygon" {
+                builder.close();
+            }
+        },
+        _ => return,
+    }
+
+    let opacity = style.opacity * style.group_opacity;
+    let fill = style.fill.map(|paint| paint.with_opacity(style.fill_opacity * opacity));
+    let stroke = style.stroke
+        .filter(|_| style.stroke_width > 0.0)
+        .map(|paint| paint.with_opacity(style.stroke_opacity * opacity));
+    if builder.path.is_empty() || (fill.is_none() && stroke.is_none()) {
+        return;
+    }
+
+    // Strokes are scaled along with the shape.
+    let stroke_scale = style.transform.matrix2.determinant().abs().sqrt();
+
+    svg.shapes.push(SaikoSvgShape {
+        outline : builder.path,
+        fill,
+        fill_rule : style.fill_rule,
+        stroke,
+        stroke_width : style.stroke_width * stroke_scale,
+        line_cap : style.line_cap,
+        line_join : style.line_join,
+    });
+}
+
+//==============================================================================
+//             SvgPathBuilder
+//==============================================================================
+
+/// Builds a [`SaikoPath`] out of svg commands, moving every point by the transform of the element.
+struct SvgPathBuilder {
+    path : SaikoPath,
+    transform : Affine2,
+    current_pos : Vec2,
+    subpath_start : Vec2,
+}
+
+impl SvgPathBuilder {
+    fn new(transform : Affine2) -> Self {
+        Self {
+            path : SaikoPath::default(),
+            transform,
+            current_pos : Vec2::ZERO,
+            subpath_start : Vec2::ZERO,
+        }
+    }
+
+    fn move_to(&mut self, point : Vec2) {
+        self.path.move_to(self.transform.transform_point2(point));
+        self.current_pos = point;
+        self.subpath_start = point;
+    }
+
+    fn line_to(&mut self, point : Vec2) {
+        self.path.line_to(self.transform.transform_point2(point));
+        self.current_pos = point;
+    }
+
+    fn quad_to(&mut self, control : Vec2, point : Vec2) {
+        self.path.quad_to(self.transform.transform_point2(control), self.transform.transform_point2(point));
+        self.current_pos = point;
+    }
+
+    fn cubic_to(&mut self, control_1 : Vec2, control_2 : Vec2, point : Vec2) {
+        self.path.cubic_to(
+            self.transform.transform_point2(control_1),
+            self.transform.transform_point2(control_2),
+            self.transform.transform_point2(point),
+        );
+        self.current_pos = point;
+    }
+
+    fn close(&mut self) {
+        self.path.close();
+        self.current_pos = self.subpath_start;
+    }
+
+    /// Draws an elliptical arc to `point` as cubic curves, following the svg arc command.
+    fn arc_to(&mut self, radii : Vec2, x_rotation : f32, large_arc : bool, sweep : bool, point : Vec2) {
+        let start = self.current_pos;
+        if start.distance_squared(point) < f32::EPSILON {
+            return;
+        }
+
+        let mut radii = radii.abs();
+        if radii.x < f32::EPSILON || radii.y < f32::EPSILON {
+            self.line_to(point);
+            return;
+        }
+
+        // Finds the center of the ellipse, following the svg implementation notes.
+        let rotation = Vec2::from_angle(x_rotation.to_radians());
+        let unrotate = Vec2::new(rotation.x, -rotation.y);
+        let prime = unrotate.rotate((start - point) * 0.5);
+
+        let lambda = (prime / radii).length_squared();
+        if lambda > 1.0 {
+            radii *= lambda.sqrt();
+        }
+
+        let rx2 = radii.x * radii.x;
+        let ry2 = radii.y * radii.y;
+        let numerator = rx2 * ry2 - rx2 * prime.y * prime.y - ry2 * prime.x * prime.x;
+        let denominator = rx2 * prime.y * prime.y + ry2 * prime.x * prime.x;
+        let mut coefficient = (numerator / denominator).max(0.0).sqrt();
+        if large_arc == sweep {
+            coefficient = -coefficient;
+        }
+        let center_prime = Vec2::new(radii.x * prime.y / radii.y, -radii.y * prime.x / radii.x) * coefficient;
+        let center = rotation.rotate(center_prime) + (start + point) * 0.5;
+
+        let start_angle = Vec2::X.angle_between((prime - center_prime) / radii);
+        let mut sweep_angle = ((prime - center_prime) / radii).angle_between((-prime - center_prime) / radii);
+        if sweep && sweep_angle < 0.0 {
+            sweep_angle += TAU;
+        } else if !sweep && sweep_angle > 0.0 {
+            sweep_angle -= TAU;
+        }
+
+        // Each cubic covers at most a quarter of the ellipse, which keeps it close to the arc.
+        let segments = (sweep_angle.abs() / FRAC_PI_2).ceil().max(1.0) as usize;
+        let step = sweep_angle / segments as f32;
+        let handle = 4.0 / 3.0 * (step / 4.0).tan();
+
+        let ellipse_point = |angle : f32| center + rotation.rotate(Vec2::from_angle(angle) * radii);
+        let ellipse_tangent = |angle : f32| rotation.rotate(Vec2::from_angle(angle).perp() * radii);
+
+        for index in 0..segments {
+            let angle_0 = start_angle + step * index as f32;
+            let angle_1 = angle_0 + step;
+            let point_0 = ellipse_point(angle_0);
+            let point_1 = if index == segments - 1 { point } else { ellipse_point(angle_1) };
+
+            self.cubic_to(
+                point_0 + ellipse_tangent(angle_0) * handle,
+                point_1 - ellipse_tangent(angle_1) * handle,
+                point_1,
+            );
+        }
+    }
+
+    fn rect(&mut self, position : Vec2, size : Vec2, radius : Vec2) {
+        if size.x <= 0.0 || size.y <= 0.0 {
+            return;
+        }
+
+        let max = position + size;
+        if radius.x <= 0.0 || radius.y <= 0.0 {
+            self.move_to(position);
+            self.line_to(Vec2::new(max.x, position.y));
+            self.line_to(max);
+            self.line_to(Vec2::new(position.x, max.y));
+            self.close();
+            return;
+        }
+
+        self.move_to(Vec2::new(position.x + radius.x, position.y));
+        self.line_to(Vec2::new(max.x - radius.x, position.y));
+        self.arc_to(radius, 0.0, false, true, Vec2::new(max.x, position.y + radius.y));
+        self.line_to(Vec2::new(max.x, max.y - radius.y));
+        self.arc_to(radius, 0.0, false, true, Vec2::new(max.x - radius.x, max.y));
+        self.line_to(Vec2::new(position.x + radius.x, max.y));
+        self.arc_to(radius, 0.0, false, true, Vec2::new(position.x, max.y - radius.y));
+        self.line_to(Vec2::new(position.x, position.y + radius.y));
+        self.arc_to(radius, 0.0, false, true, Vec2::new(position.x + radius.x, position.y));
+        self.close();
+    }
+
+    fn ellipse(&mut self, center : Vec2, radii : Vec2) {
+        if radii.x <= 0.0 || radii.y <= 0.0 {
+            return;
+        }
+
+        self.move_to(center + Vec2::new(radii.x, 0.0));
+        self.arc_to(radii, 0.0, false, true, center - Vec2::new(radii.x, 0.0));
+        self.arc_to(radii, 0.0, false, true, center + Vec2::new(radii.x, 0.0));
+        self.close();
+    }
+
+    /// Follows the commands of a path's `d` attribute. Drawing stops at the first command that
+    /// can't be read, like it does in browsers.
+    fn path_data(&mut self, data : &str) {
+        let mut numbers = SvgNumbers::new(data);
+        let mut command = b'M';
+        // The second control point of the last curve, for the smooth curve commands.
+        let mut last_control : Option<(u8, Vec2)> = None;
+
+        loop {
+            numbers.skip_separators();
+            let Some(&next) = numbers.source.get(numbers.index) else { break };
+
+            if next.is_ascii_alphabetic() {
+                command = next;
+                numbers.index += 1;
+            } else if command == b'M' {
+                // Points after a move are lines.
+                command = b'L';
+            } else if command == b'm' {
+                command = b'l';
+            } else if matches!(command, b'Z' | b'z') {
+                // Numbers can't follow a close, so the path stops there.
+                break;
+            }
+
+            let relative = command.is_ascii_lowercase();
+            let origin = if relative { self.current_pos } else { Vec2::ZERO };
+            let point = |numbers : &mut SvgNumbers| -> Option<Vec2> {
+                Some(Vec2::new(numbers.next()?, numbers.next()?) + origin)
+            };
+
+            let control = match command.to_ascii_uppercase() {
+                b'M' => {
+                    let Some(point) = point(&mut numbers) else { break };
+                    self.move_to(point);
+                    None
+                },
+                b'L' => {
+                    let Some(point) = point(&mut numbers) else { break };
+                    self.line_to(point);
+                    None
+                },
+                b'H' => {
+                    let Some(x) = numbers.next() else { break };
+                    let x = if relative { x + self.current_pos.x } else { x };
+                    self.line_to(Vec2::new(x, self.current_pos.y));
+                    None
+                },
+                b'V' => {
+                    let Some(y) = numbers.next() else { break };
+                    let y = if relative { y + self.current_pos.y } else { y };
+                    self.line_to(Vec2::new(self.current_pos.x, y));
+                    None
+                },
+                b'C' => {
+                    let (Some(control_1), Some(control_2), Some(end)) = (point(&mut numbers), point(&mut numbers), point(&mut numbers)) else { break };
+                    self.cubic_to(control_1, control_2, end);
+                    Some((b'C', control_2))
+                },
+                b'S' => {
+                    let (Some(control_2), Some(end)) = (point(&mut numbers), point(&mut numbers)) else { break };
+                    let control_1 = self.reflect(last_control, b'C');
+                    self.cubic_to(control_1, control_2, end);
+                    Some((b'C', control_2))
+                },
+                b'Q' => {
+                    let (Some(control), Some(end)) = (point(&mut numbers), point(&mut numbers)) else { break };
+                    self.quad_to(control, end);
+                    Some((b'Q', control))
+                },
+                b'T' => {
+                    let Some(end) = point(&mut numbers) else { break };
+                    let control = self.reflect(last_control, b'Q');
+                    self.quad_to(control, end);
+                    Some((b'Q', control))
+                },
+                b'A' => {
+                    let (Some(rx), Some(ry), Some(x_rotation), Some(large_arc), Some(sweep)) =
+                        (numbers.next(), numbers.next(), numbers.next(), numbers.next_flag(), numbers.next_flag()) else { break };
+                    let Some(end) = point(&mut numbers) else { break };
+                    self.arc_to(Vec2::new(rx, ry), x_rotation, large_arc, sweep, end);
+                    None
+                },
+                b'Z' => {
+                    self.close();
+                    None
+                },
+                _ => break,
+            };
+            last_control = control;
+        }
+    }
+
+    /// The first control point of a smooth curve, which mirrors the last control point of the
+    /// curve before it if that curve was of the same kind.
+    fn reflect(&self, last_control : Option<(u8, Vec2)>, kind : u8) -> Vec2 {
+        match last_control {
+            Some((last_kind, control)) if last_kind == kind => self.current_pos * 2.0 - control,
+            _ => self.current_pos,
+        }
+    }
+}
+
+//==============================================================================
+//             SvgNumbers
+//==============================================================================
+
+/// Reads the numbers out of a list, which can be split by whitespace, commas, or nothing at all
+/// when the next number starts with a sign or a second decimal point.
+struct SvgNumbers<'a> {
+    source : &'a [u8],
+    index : usize,
+}
+
+impl<'a> SvgNumbers<'a> {
+    fn new(source : &'a str) -> Self {
+        Self { source : source.as_bytes(), index : 0 }
+    }
+
+    fn skip_separators(&mut self) {
+        while self.source.get(self.index).is_some_and(|c| c.is_ascii_whitespace() || *c == b',') {
+            self.index += 1;
+        }
+    }
+
+    /// Arc flags are a single digit, and don't need anything between them and the next number.
+    fn next_flag(&mut self) -> Option<bool> {
+        self.skip_separators();
+        let flag = match self.source.get(self.index)? {
+            b'0' => false,
+            b'1' => true,
+            _ => return None,
+        };
+        self.index += 1;
+        Some(flag)
+    }
+}
+
+impl Iterator for SvgNumbers<'_> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        self.skip_separators();
+        let start = self.index;
+        let digits = |source : &[u8], mut index : usize| {
+            while source.get(index).is_some_and(u8::is_ascii_digit) {
+                index += 1;
+            }
+            index
+        };
+
+        let mut index = start;
+        if matches!(self.source.get(index), Some(b'+' | b'-')) {
+            index += 1;
+        }
+        index = digits(self.source, index);
+        if self.source.get(index) == Some(&b'.') {
+            index = digits(self.source, index + 1);
+        }
+        if matches!(self.source.get(index), Some(b'e' | b'E')) {
+            let mut exponent = index + 1;
+            if matches!(self.source.get(exponent), Some(b'+' | b'-')) {
+                exponent += 1;
+            }
+            if self.source.get(exponent).is_some_and(u8::is_ascii_digit) {
+                index = digits(self.source, exponent);
+            }
+        }
+
+        let number = std::str::from_utf8(&self.source[start..index]).ok()?.parse().ok()?;
+        self.index = index;
+        Some(number)
+    }
+}
+
+//==============================================================================
+//             Tests
+//==============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(data : &str) -> SvgPathBuilder {
+        let mut builder = SvgPathBuilder::new(Affine2::IDENTITY);
+        builder.path_data(data);
+        builder
+    }
+
+    fn assert_near(a : Vec2, b : Vec2) {
+        assert!(a.distance(b) < 1e-4, "{a} is not {b}");
+    }
+
+    #[test]
+    fn numbers_without_separators() {
+        let numbers = SvgNumbers::new("1-2.5.5 3e2,-.5").collect::<Vec<_>>();
+        assert_eq!(numbers, vec![1.0, -2.5, 0.5, 300.0, -0.5]);
+
+        let builder = path("M1-2.5.5.5");
+        assert_eq!(builder.path.curve_ends(), vec![Vec2::new(0.5, 0.5)]);
+    }
+
+    #[test]
+    fn relative_commands() {
+        let builder = path("m10 10l5 0 0 5h-5v-5z");
+        assert_eq!(builder.path.curve_ends(), vec![
+            Vec2::new(15.0, 10.0),
+            Vec2::new(15.0, 15.0),
+            Vec2::new(10.0, 15.0),
+            Vec2::new(10.0, 10.0),
+        ]);
+
+        // Points after a relative move are relative lines.
+        let builder = path("m1 1 2 2 1 0");
+        assert_eq!(builder.path.curve_ends(), vec![Vec2::new(3.0, 3.0), Vec2::new(4.0, 3.0)]);
+    }
+
+    #[test]
+    fn arc_flags_without_separators() {
+        let packed = path("M0 0a5 5 0 1010 0");
+        let separated = path("M0 0a5 5 0 1 0 10 0");
+        assert_near(packed.current_pos, Vec2::new(10.0, 0.0));
+        assert_eq!(packed.path.curve_ends(), separated.path.curve_ends());
+
+        // A half circle from the left to the right of the center goes through the top or bottom.
+        let arc = path("M0 0A5 5 0 0 1 10 0");
+        let ends = arc.path.curve_ends();
+        assert!(ends.iter().any(|end| (end.y.abs() - 5.0).abs() < 1e-3));
+        assert_near(*ends.last().unwrap(), Vec2::new(10.0, 0.0));
+    }
+
+    #[test]
+    fn numbers_after_close_stop_the_path() {
+        let builder = path("M0 0L1 1Z5 5");
+        assert_eq!(builder.path.curve_ends(), vec![Vec2::new(1.0, 1.0), Vec2::ZERO]);
+        assert_eq!(builder.current_pos, Vec2::ZERO);
+    }
+
+    #[test]
+    fn group_opacity() {
+        let svg = parse_svg(r#"
+            <svg viewBox="0 0 10 10">
+                <g opacity="0.5">
+                    <g><path d="M0 0L10 0L10 10Z" opacity="0.5"/></g>
+                    <rect width="10" height="10"/>
+                </g>
+                <circle r="5"/>
+            </svg>
+        "#).unwrap();
+
+        let opacities = svg.shapes
+            .iter()
+            .map(|shape| match shape.fill {
+                Some(SaikoSvgPaint::CurrentColor(opacity)) => opacity,
+                _ => panic!("the shape is not filled with the tint"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(opacities, vec![0.25, 0.5, 1.0]);
+    }
+
+    #[test]
+    fn transform_lists() {
+        let transform = parse_transform("translate(10,0), scale(2)");
+        assert_near(transform.transform_point2(Vec2::ONE), Vec2::new(12.0, 2.0));
+
+        let transform = parse_transform("rotate(90 1 1)translate(1)");
+        assert_near(transform.transform_point2(Vec2::new(1.0, 1.0)), Vec2::new(1.0, 2.0));
+    }
+
+    #[test]
+    fn size_without_view_box() {
+        let svg = parse_svg(r#"<svg width="24px" height="12"><rect width="24" height="12"/></svg>"#).unwrap();
+        assert_eq!(svg.size, Vec2::new(24.0, 12.0));
+
+        // The top left corner of the svg is moved to the top left of the center, with y pointing up.
+        assert_near(svg.shapes[0].outline.curve_ends()[3], Vec2::new(-12.0, 6.0));
+
+        assert!(matches!(parse_svg("<svg><rect width=\"1\" height=\"1\"/></svg>"), Err(SaikoSvgError::MissingSize)));
+    }
+}
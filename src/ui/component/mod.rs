@@ -11,11 +11,18 @@ use std::marker::PhantomData;
 use bevy::{
     prelude::*,
     render::{view::RenderLayers, Extract, RenderApp},
+    utils::HashSet,
+    window::PrimaryWindow,
 };
 
-use crate::render::{SaikoRenderState, SaikoRenderTarget};
+use crate::{common::{bounds::Bounds, util::find_ancestor}, render::{camera_window, image_target::SaikoImageTarget, SaikoCamera, SaikoRenderState, SaikoRenderTarget, SaikoWindowRoot}};
 
-use super::{context::SaikoRenderContext, node::SaikoNode};
+use super::{
+    context::{SaikoRenderContext, SaikoRenderContextExtention},
+    drag::{SaikoDragState, DRAG_GHOST_OPACITY, DRAG_GHOST_Z_INDEX},
+    focus::SaikoFocus,
+    node::SaikoNode,
+};
 
 //==============================================================================
 //          SaikoComponent
@@ -62,10 +69,23 @@ impl<T: SaikoComponent> Default for SaikoComponentPlugin<T> {
 
 fn extract_components<T: SaikoComponent>(
     mut render_targets: Query<(&mut SaikoRenderTarget, Option<&RenderLayers>)>,
-    query: Extract<Query<(&T, &SaikoNode, Option<&RenderLayers>, Option<&InheritedVisibility>)>>,
+    query: Extract<Query<(Entity, &T, &SaikoNode, Option<&RenderLayers>, Option<&InheritedVisibility>)>>,
+    parents: Extract<Query<&Parent>>,
+    image_targets: Extract<Query<(), With<SaikoImageTarget>>>,
+    window_roots: Extract<Query<&SaikoWindowRoot>>,
+    cameras: Extract<Query<&SaikoCamera>>,
+    primary_window: Extract<Query<Entity, With<PrimaryWindow>>>,
+    focus: Extract<Res<SaikoFocus>>,
+    drag: Extract<Res<SaikoDragState>>,
 ) {
+    let camera_roots = cameras
+        .iter()
+        .filter_map(|camera| camera.root)
+        .collect::<HashSet<_>>();
+    let primary_window = primary_window.get_single().ok();
+    
     for (mut render_target, render_target_layers) in render_targets.iter_mut() {
-        for (component, node, component_render_layers, component_visability) in query.iter() {
+        for (entity, component, node, component_render_layers, component_visability) in query.iter() {
             let visable = component_visability.map_or(true, |v| v.get());
             let on_layer = match (render_target_layers, component_render_layers) {
                 (Some(render_layers), Some(component_render_layers)) => {
@@ -75,23 +95,78 @@ fn extract_components<T: SaikoComponent>(
                 _ => true,
             };
 
-            if on_layer && visable {
-                println!("Rendering Component with bounds {:?}", node.bounds());
-                let mut render_context = SaikoRenderContext::new(&mut render_target.1, *node.bounds());
+            //Targets with a root only draw their own subtree, and the rest draw the nodes of their window.
+            let root = find_ancestor(entity, &parents, |entity| {
+                image_targets.contains(entity) || camera_roots.contains(&entity) || window_roots.contains(entity)
+            });
+            let window_root = root.and_then(|root| window_roots.get(root).ok());
+            let in_target = match (render_target.root, window_root) {
+                (Some(target_root), _) => root == Some(target_root),
+                (None, Some(window_root)) => on_layer && render_target.window == Some(window_root.window),
+                //Nodes without a root belong to the primary window, and to cameras that are not in a window.
+                (None, None) => root.is_none() && on_layer && (render_target.window.is_none() || render_target.window == primary_window),
+            };
+
+            if in_target && visable {
+                trace!("Rendering Component with bounds {:?}", node.bounds());
+                let is_focused = focus.is_visible() && focus.focused() == Some(entity);
+                let mut render_context = SaikoRenderContext::new(&mut render_target.buffer, *node.bounds()).focused(is_focused);
                 component.render(&mut render_context);
             }
+            
+            //The dragged node is drawn again at the pointer, over everything else in its window.
+            let ghost = drag.ghost().filter(|ghost| ghost.entity == entity && on_layer && render_target.root.is_none() && render_target.window == Some(ghost.window));
+            if let Some(ghost) = ghost {
+                let bounds = Bounds::new(ghost.position + ghost.offset, node.bounds().size, DRAG_GHOST_Z_INDEX);
+                SaikoRenderContext::new(&mut render_target.buffer, bounds)
+                    .layer(DRAG_GHOST_OPACITY, |render_context| component.render(render_context));
+            }
         }
     }
 }
 
+/// Changes inside of an image target only redraw that image target, and every other change only
+/// redraws the window the component is in.
 fn component_change_detection<T: SaikoComponent>(
     mut render_state : ResMut<SaikoRenderState>,
-    components : Query<(Ref<T>, Ref<SaikoNode>)>
+    mut image_targets : Query<&mut SaikoImageTarget>,
+    window_roots : Query<&SaikoWindowRoot>,
+    cameras : Query<(&Camera, &SaikoCamera)>,
+    primary_window : Query<Entity, With<PrimaryWindow>>,
+    components : Query<(Entity, Ref<T>, Ref<SaikoNode>)>,
+    parents : Query<&Parent>,
 ) {
-    for (component, node) in components.iter() {
+    let primary_window = primary_window.get_single().ok();
+    
+    for (entity, component, node) in components.iter() {
         if T::should_auto_update() && (node.is_changed() || component.is_changed()) {
             println!("Component Changed");
-            render_state.mark_dirty();
+            let root = find_ancestor(entity, &parents, |entity| {
+                image_targets.contains(entity)
+                    || window_roots.contains(entity)
+                    || cameras.iter().any(|(_, saiko_camera)| saiko_camera.root == Some(entity))
+            });
+            
+            if let Some(mut image_target) = root.and_then(|root| image_targets.get_mut(root).ok()) {
+                image_target.mark_dirty();
+                continue;
+            }
+            
+            let window = match root {
+                Some(root) => match window_roots.get(root) {
+                    Ok(window_root) => Some(window_root.window),
+                    Err(_) => cameras
+                        .iter()
+                        .find(|(_, saiko_camera)| saiko_camera.root == Some(root))
+                        .and_then(|(camera, _)| camera_window(camera, primary_window)),
+                },
+                None => primary_window,
+            };
+            
+            match window {
+                Some(window) => render_state.mark_window_dirty(window),
+                None => render_state.mark_dirty(),
+            }
         }
     }
 }
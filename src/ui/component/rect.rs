@@ -41,6 +41,7 @@ impl SaikoComponent for RectComponent {
                 .color(self.background_color)
                 .border_color(self.border_color)
                 .border_width(self.border_thickness)
+                .focus_ring(3.0, self.border_thickness, Color::WHITE)
             .relative(100.0 + self.border_thickness / 2.0, 0.0, 100.0, 100.0).rect()
                 .border_radius(self.border_radius)
                 .color(self.background_color)
@@ -3,20 +3,29 @@
 //             SaikoRenderContext
 //==============================================================================
 
-use bevy::{math::{Vec2, Vec4}, render::color::Color};
+use std::sync::Once;
 
-use crate::{common::{bounds::Bounds, value::{Percent, Value}}, render::buffer::{BorderStyleBuffer, FillStyleBuffer, RectBuffer, SaikoBuffer}};
+use bevy::{asset::AssetId, log::warn, math::{Rect, Vec2, Vec4}, render::{color::Color, texture::Image}};
+
+use crate::{common::{bounds::Bounds, value::{Percent, Value}}, render::{buffer::{BackdropBuffer, BorderAlignment, BorderPattern, BorderStyleBuffer, EllipseBuffer, FillStyleBuffer, FillType, FillRule, IconBuffer, ImageBuffer, ImageFit, LineCap, LineJoin, NineSlice, NineSliceBuffer, NineSliceMode, PathBuffer, RectBuffer, SaikoBuffer, ShadowStyleBuffer, TextBuffer}, font::sdf::SaikoFontSdf, path::SaikoPath, svg::SaikoSvg}};
 
 use super::position::RelativePosition;
 
 pub struct SaikoRenderContext<'r> {
     buffer : &'r mut SaikoBuffer,
-    bounds : Bounds
+    bounds : Bounds,
+    is_focused : bool,
 }
 
 impl <'r> SaikoRenderContext<'r> {
     pub fn new(buffer: &'r mut SaikoBuffer, bounds: Bounds) -> Self {
-        Self { buffer, bounds }
+        Self { buffer, bounds, is_focused: false }
+    }
+    
+    /// Marks the node being drawn as focused, which shows the focus rings of its rects.
+    pub fn focused(mut self, is_focused: bool) -> Self {
+        self.is_focused = is_focused;
+        self
     }
     
     pub fn len(&self) -> usize {
@@ -32,6 +41,10 @@ impl <'r> SaikoRenderContextExtention for SaikoRenderContext<'r> {
     fn get_buffer(&mut self) -> &mut SaikoBuffer {
         self.buffer
     }
+    
+    fn is_focused(&self) -> bool {
+        self.is_focused
+    }
 }
 
 impl <'r> Drop for SaikoRenderContext<'r> {
@@ -47,6 +60,16 @@ pub trait SaikoRenderContextExtention: Drop {
     
     fn get_buffer(&mut self) -> &mut SaikoBuffer;
     
+    /// The corner radius that children are clipped to by [`SaikoRenderContextExtention::clip`].
+    fn clip_radius(&self) -> Vec4 {
+        Vec4::ZERO
+    }
+    
+    /// Whether the node being drawn has keyboard focus.
+    fn is_focused(&self) -> bool {
+        false
+    }
+    
     fn width(&self) -> f32 {
         self.get_bounds().size.x
     }
@@ -58,21 +81,146 @@ pub trait SaikoRenderContextExtention: Drop {
     fn rect(&mut self) -> SaikoRenderContextRectStyler<'_> {
         SaikoRenderContextRectStyler {
             bounds: *self.get_bounds(),
+            is_focused: self.is_focused(),
             buffer: self.get_buffer(),
             border_style: BorderStyleBuffer::default(),
             fill_style: FillStyleBuffer::default(),
+            shadow_style: ShadowStyleBuffer::default(),
+            inner_shadow_style: ShadowStyleBuffer::default(),
+            nine_slice: NineSlice::default(),
+            backdrop_blur: 0.0,
+            focus_ring: None,
+            is_pushed: false,
+        }
+    }
+    
+    fn text(&mut self, text : impl Into<String>) -> SaikoRenderContextTextStyler<'_> {
+        SaikoRenderContextTextStyler {
+            bounds: *self.get_bounds(),
+            buffer: self.get_buffer(),
+            text: text.into(),
+            font: AssetId::default(),
+            font_size: 16.0,
+            color: Color::WHITE,
+            is_pushed: false,
+        }
+    }
+    
+    fn image(&mut self, image : impl Into<AssetId<Image>>) -> SaikoRenderContextImageStyler<'_> {
+        SaikoRenderContextImageStyler {
+            bounds: *self.get_bounds(),
+            buffer: self.get_buffer(),
+            image: image.into(),
+            uv_rect: Rect::new(0.0, 0.0, 1.0, 1.0),
+            tint: Color::WHITE,
+            fit: ImageFit::default(),
+            border_radius: Vec4::ZERO,
+            is_pushed: false,
         }
     }
     
+    /// Draws an svg icon in the middle of these bounds, scaled to fit a square that is `size` pixels
+    /// wide. The parts of the icon that use `currentColor`, or that don't set a fill, are drawn
+    /// with the tint.
+    fn icon(&mut self, icon : impl Into<AssetId<SaikoSvg>>, size : f32, tint : impl Into<Color>) {
+        let mut bounds = *self.get_bounds();
+        bounds.size = Vec2::splat(size * 0.5);
+        
+        self.get_buffer().push_icon(IconBuffer {
+            bound : bounds,
+            icon : icon.into(),
+            tint : tint.into(),
+            clip : 0,
+        });
+    }
+    
+    /// Draws a circle in the middle of these bounds, as big as fits inside of them.
+    fn circle(&mut self) -> SaikoRenderContextEllipseStyler<'_> {
+        let mut bounds = *self.get_bounds();
+        bounds.size = Vec2::splat(bounds.size.min_element());
+        
+        SaikoRenderContextEllipseStyler {
+            bounds,
+            buffer: self.get_buffer(),
+            ellipse: EllipseBuffer::default(),
+            is_pushed: false,
+        }
+    }
+    
+    /// Draws an ellipse that fills these bounds.
+    fn ellipse(&mut self) -> SaikoRenderContextEllipseStyler<'_> {
+        SaikoRenderContextEllipseStyler {
+            bounds: *self.get_bounds(),
+            buffer: self.get_buffer(),
+            ellipse: EllipseBuffer::default(),
+            is_pushed: false,
+        }
+    }
+    
+    /// Draws a path. The points of the path are in pixels, relative to the center of these
+    /// bounds with y pointing up. By default the path is stroked with a thin white line.
+    fn path(&mut self) -> SaikoRenderContextPathStyler<'_> {
+        SaikoRenderContextPathStyler {
+            bounds: *self.get_bounds(),
+            buffer: self.get_buffer(),
+            outline: SaikoPath::default(),
+            fill_color: Color::NONE,
+            fill_rule: FillRule::default(),
+            stroke_color: Color::WHITE,
+            stroke_width: 1.0,
+            line_cap: LineCap::default(),
+            line_join: LineJoin::default(),
+            is_pushed: false,
+        }
+    }
+    
+    /// Clips everything drawn inside of `f` to these bounds, following the rounded corners of a
+    /// rect. Clips inside of other clips only draw where both overlap.
+    fn clip(&mut self, f : impl FnOnce(&mut SaikoRenderContext<'_>)) {
+        let bounds = *self.get_bounds();
+        let border_radius = self.clip_radius();
+        let is_focused = self.is_focused();
+        let buffer = self.get_buffer();
+        
+        buffer.push_clip(bounds, border_radius);
+        f(&mut SaikoRenderContext::new(buffer, bounds).focused(is_focused));
+        buffer.pop_clip();
+    }
+    
+    /// Draws everything inside of `f` into its own texture, which is then drawn with `opacity`.
+    /// Overlapping children fade out together, instead of showing through each other.
+    fn layer(&mut self, opacity : f32, f : impl FnOnce(&mut SaikoRenderContext<'_>)) {
+        let bounds = *self.get_bounds();
+        let is_focused = self.is_focused();
+        let buffer = self.get_buffer();
+        
+        buffer.push_layer(bounds, opacity);
+        f(&mut SaikoRenderContext::new(buffer, bounds).focused(is_focused));
+        buffer.pop_layer();
+    }
+    
     fn relative(&mut self, x : f32, y : f32, width : f32, height : f32) -> SaikoRenderContext<'_> {
         let bounds = Bounds::new(
             Vec2::new(x, y),
             Vec2::new(width, height),
-            self.get_bounds().z_index
+            0
         );
         
         SaikoRenderContext {
             bounds: RelativePosition::create_relative(self.get_bounds(), &bounds),
+            is_focused: self.is_focused(),
+            buffer: self.get_buffer(),
+        }
+    }
+    
+    /// Everything drawn with the returned context is raised by `z_index` above the current context.
+    fn with_z_index(&mut self, z_index : i32) -> SaikoRenderContext<'_> {
+        let mut bounds = *self.get_bounds();
+        bounds.z_index += z_index;
+        
+        SaikoRenderContext {
+            bounds,
+            is_focused: self.is_focused(),
             buffer: self.get_buffer(),
         }
     }
@@ -80,6 +228,7 @@ pub trait SaikoRenderContextExtention: Drop {
     fn align(&mut self, horizontal : impl Into<Percent>, vertical : impl Into<Percent>, width : impl Into<Value>, height : impl Into<Value>) -> SaikoRenderContext<'_> {
         SaikoRenderContext {
             bounds: RelativePosition::create_align(self.get_bounds(), horizontal, vertical, width, height),
+            is_focused: self.is_focused(),
             buffer: self.get_buffer(),
         }
     }
@@ -129,23 +278,110 @@ pub struct SaikoRenderContextRectStyler<'r> {
     buffer : &'r mut SaikoBuffer,
     bounds : Bounds,
     border_style : BorderStyleBuffer,
-    fill_style : FillStyleBuffer
+    fill_style : FillStyleBuffer,
+    shadow_style : ShadowStyleBuffer,
+    inner_shadow_style : ShadowStyleBuffer,
+    nine_slice : NineSlice,
+    backdrop_blur : f32,
+    focus_ring : Option<FocusRing>,
+    is_focused : bool,
+    is_pushed : bool,
+}
+
+/// The outline drawn around a focused rect.
+#[derive(Clone, Copy)]
+struct FocusRing {
+    width : f32,
+    offset : f32,
+    color : Color,
 }
 
 impl <'r> SaikoRenderContextRectStyler<'r> {
 
     pub fn color(mut self, color : impl Into<Color>) -> Self {
+        self.fill_style.fill_type = FillType::Solid as u32;
         self.fill_style.fill_color = color.into();
         self
     }
     
+    /// Fills the rect with a gradient along `angle`, in radians. An angle of 0 goes from left to
+    /// right, and it turns counter clockwise. The stops are an offset between 0 and 1 and a color.
+    pub fn linear_gradient(mut self, angle : f32, stops : impl IntoIterator<Item = (f32, Color)>) -> Self {
+        self.fill_style.fill_type = FillType::LinearGradient as u32;
+        self.fill_style.gradient_angle = angle;
+        self.fill_style.set_stops(stops);
+        self
+    }
+    
+    /// Fills the rect with a gradient that spreads out from `center`, reaching the last stop at
+    /// the farthest corner. The center goes from (0, 0) at the bottom left to (1, 1) at the top right.
+    pub fn radial_gradient(mut self, center : impl Into<Vec2>, stops : impl IntoIterator<Item = (f32, Color)>) -> Self {
+        self.fill_style.fill_type = FillType::RadialGradient as u32;
+        self.fill_style.gradient_center = center.into();
+        self.fill_style.set_stops(stops);
+        self
+    }
+    
+    /// Fills the rect with a gradient that sweeps around `center`, starting at `angle` in radians.
+    pub fn conic_gradient(mut self, center : impl Into<Vec2>, angle : f32, stops : impl IntoIterator<Item = (f32, Color)>) -> Self {
+        self.fill_style.fill_type = FillType::ConicGradient as u32;
+        self.fill_style.gradient_center = center.into();
+        self.fill_style.gradient_angle = angle;
+        self.fill_style.set_stops(stops);
+        self
+    }
+    
+    /// Casts a drop shadow behind the rect. The shadow is moved by `offset`, blurred by `blur` pixels
+    /// and grown on every side by `spread` pixels.
+    pub fn shadow(mut self, offset : impl Into<Vec2>, blur : f32, spread : f32, color : impl Into<Color>) -> Self {
+        self.shadow_style = ShadowStyleBuffer::new(offset.into(), blur, spread, color.into());
+        self
+    }
+    
+    /// Casts a shadow inwards from the edge of the rect, drawn over the fill and under the border.
+    pub fn inner_shadow(mut self, offset : impl Into<Vec2>, blur : f32, spread : f32, color : impl Into<Color>) -> Self {
+        self.inner_shadow_style = ShadowStyleBuffer::new(offset.into(), blur, spread, color.into());
+        self
+    }
+    
     pub fn border_color(mut self, color : impl Into<Color>) -> Self {
         self.border_style.border_color = color.into();
         self
     }
     
     pub fn border_width(mut self, width : f32) -> Self {
-        self.border_style.border_width = width;
+        self.border_style.border_width = Vec4::splat(width);
+        self
+    }
+    
+    /// Sets the width of each side of the border. A width of 0 leaves that side without a border.
+    pub fn border_widths(mut self, top : f32, right : f32, bottom : f32, left : f32) -> Self {
+        self.border_style.border_width = Vec4::new(top, right, bottom, left);
+        self
+    }
+    
+    pub fn border_alignment(mut self, alignment : BorderAlignment) -> Self {
+        self.border_style.border_alignment = alignment as u32;
+        self
+    }
+    
+    pub fn border_solid(mut self) -> Self {
+        self.border_style.border_pattern = BorderPattern::Solid as u32;
+        self
+    }
+    
+    /// Breaks the border into dashes of `dash_length` pixels, with `gap` pixels between them.
+    pub fn border_dashed(mut self, dash_length : f32, gap : f32) -> Self {
+        self.border_style.border_pattern = BorderPattern::Dashed as u32;
+        self.border_style.dash_length = dash_length;
+        self.border_style.dash_gap = gap;
+        self
+    }
+    
+    /// Breaks the border into round dots as wide as the border, with `gap` pixels between them.
+    pub fn border_dotted(mut self, gap : f32) -> Self {
+        self.border_style.border_pattern = BorderPattern::Dotted as u32;
+        self.border_style.dash_gap = gap;
         self
     }
     
@@ -153,6 +389,111 @@ impl <'r> SaikoRenderContextRectStyler<'r> {
         self.border_style.border_radius = radius.into();
         self
     }
+    
+    /// Fills the rect with a nine-slice image. The slice is how far the corners reach into the
+    /// image, in pixels or as a percent of the image size.
+    pub fn nine_slice(self, image : impl Into<AssetId<Image>>, slice : impl Into<Value>) -> Self {
+        let slice = slice.into();
+        self.nine_slice_insets(image, slice, slice, slice, slice)
+    }
+    
+    /// Fills the rect with a nine-slice image, with a different slice inset for each side.
+    pub fn nine_slice_insets(
+        mut self,
+        image : impl Into<AssetId<Image>>,
+        top : impl Into<Value>,
+        right : impl Into<Value>,
+        bottom : impl Into<Value>,
+        left : impl Into<Value>,
+    ) -> Self {
+        self.fill_style.fill_type = FillType::NineSlice as u32;
+        self.nine_slice.image = image.into();
+        self.nine_slice.slice = [top.into(), right.into(), bottom.into(), left.into()];
+        self
+    }
+    
+    /// Sets whether the edges and the center of the nine-slice image are stretched or tiled.
+    pub fn nine_slice_mode(mut self, edges : NineSliceMode, center : NineSliceMode) -> Self {
+        self.nine_slice.edge_mode = edges;
+        self.nine_slice.center_mode = center;
+        self
+    }
+    
+    /// Scales the nine-slice image, so the corners can be drawn bigger or smaller than the image.
+    pub fn nine_slice_scale(mut self, scale : f32) -> Self {
+        self.nine_slice.scale = scale;
+        self
+    }
+    
+    /// Blurs the scene behind the rect by roughly `radius` pixels. Give the rect a translucent
+    /// fill to tint the blur. Only the scene is blurred, not the ui that is drawn under the rect.
+    pub fn backdrop_blur(mut self, radius : f32) -> Self {
+        self.backdrop_blur = radius;
+        self
+    }
+    
+    /// Outlines the rect while its node has keyboard focus. The ring is `width` pixels wide and
+    /// drawn `offset` pixels outside of the rect, following its rounded corners.
+    pub fn focus_ring(mut self, width : f32, offset : f32, color : impl Into<Color>) -> Self {
+        self.focus_ring = Some(FocusRing { width, offset, color : color.into() });
+        self
+    }
+}
+
+impl <'r> SaikoRenderContextRectStyler<'r> {
+    /// Pushes the rect to the buffer, if it has not been already.
+    fn push(&mut self) {
+        if self.is_pushed { return }
+        self.is_pushed = true;
+        
+        let rect = RectBuffer {
+            bound : self.bounds,
+            border_style: self.border_style,
+            fill_style: self.fill_style,
+            shadow_style: self.shadow_style,
+            inner_shadow_style: self.inner_shadow_style,
+            nine_slice: NineSliceBuffer::default(),
+            clip: 0,
+        };
+        
+        if self.backdrop_blur > 0.0 {
+            self.buffer.push_backdrop(BackdropBuffer {
+                bound : self.bounds,
+                border_radius : self.border_style.border_radius,
+                blur_radius : self.backdrop_blur,
+                opacity : 1.0,
+                clip : 0,
+            });
+        }
+        
+        if self.fill_style.fill_type == FillType::NineSlice as u32 {
+            self.buffer.push_nine_slice_rect(rect, self.nine_slice);
+        } else {
+            self.buffer.push_rect(rect);
+        }
+        
+        if let (true, Some(ring)) = (self.is_focused, self.focus_ring) {
+            let radius = self.border_style.border_radius;
+            let mut bound = self.bounds;
+            bound.size += Vec2::splat(ring.offset);
+            
+            self.buffer.push_rect(RectBuffer {
+                bound,
+                border_style: BorderStyleBuffer {
+                    border_color: ring.color,
+                    border_width: Vec4::splat(ring.width),
+                    border_radius: Vec4::select(radius.cmpgt(Vec4::ZERO), radius + ring.offset, Vec4::ZERO),
+                    border_alignment: BorderAlignment::Outside as u32,
+                    ..Default::default()
+                },
+                fill_style: FillStyleBuffer { fill_color: Color::NONE, ..Default::default() },
+                shadow_style: ShadowStyleBuffer::default(),
+                inner_shadow_style: ShadowStyleBuffer::default(),
+                nine_slice: NineSliceBuffer::default(),
+                clip: 0,
+            });
+        }
+    }
 }
 
 impl <'r> SaikoRenderContextExtention for SaikoRenderContextRectStyler<'r> {
@@ -160,17 +501,353 @@ impl <'r> SaikoRenderContextExtention for SaikoRenderContextRectStyler<'r> {
         &self.bounds
     }
     
+    /// The rect is pushed before any of its children, so they are drawn on top of it.
     fn get_buffer(&mut self) -> &mut SaikoBuffer {
+        self.push();
         self.buffer
     }
+    
+    fn clip_radius(&self) -> Vec4 {
+        self.border_style.border_radius
+    }
+    
+    fn is_focused(&self) -> bool {
+        self.is_focused
+    }
 }
 
 impl Drop for SaikoRenderContextRectStyler<'_> {
     fn drop(&mut self) {
-        self.buffer.push_rect(RectBuffer {
+        self.push();
+    }
+}
+
+//==============================================================================
+//             SaikoRenderContextTextStyler Trait
+//==============================================================================
+
+pub struct SaikoRenderContextTextStyler<'r> {
+    buffer : &'r mut SaikoBuffer,
+    bounds : Bounds,
+    text : String,
+    font : AssetId<SaikoFontSdf>,
+    font_size : f32,
+    color : Color,
+    is_pushed : bool,
+}
+
+impl <'r> SaikoRenderContextTextStyler<'r> {
+    
+    pub fn font(mut self, font : impl Into<AssetId<SaikoFontSdf>>) -> Self {
+        self.font = font.into();
+        self
+    }
+    
+    pub fn font_size(mut self, size : f32) -> Self {
+        self.font_size = size;
+        self
+    }
+    
+    pub fn color(mut self, color : impl Into<Color>) -> Self {
+        self.color = color.into();
+        self
+    }
+}
+
+impl <'r> SaikoRenderContextTextStyler<'r> {
+    /// Pushes the text to the buffer, if it has not been already.
+    fn push(&mut self) {
+        if self.is_pushed { return }
+        self.is_pushed = true;
+        
+        if self.font == AssetId::default() {
+            static MISSING_FONT : Once = Once::new();
+            MISSING_FONT.call_once(|| warn!("Text was drawn without a font, so it is not shown. Set one with `.font()`."));
+        }
+        
+        self.buffer.push_text(TextBuffer {
             bound : self.bounds,
-            border_style: self.border_style,
-            fill_style: self.fill_style
+            text : std::mem::take(&mut self.text),
+            font : self.font,
+            font_size : self.font_size,
+            color : self.color,
+            clip : 0,
+        });
+    }
+}
+
+impl <'r> SaikoRenderContextExtention for SaikoRenderContextTextStyler<'r> {
+    fn get_bounds(&self) -> &Bounds {
+        &self.bounds
+    }
+    
+    /// The text is pushed before any of its children, so they are drawn on top of it.
+    fn get_buffer(&mut self) -> &mut SaikoBuffer {
+        self.push();
+        self.buffer
+    }
+}
+
+impl Drop for SaikoRenderContextTextStyler<'_> {
+    fn drop(&mut self) {
+        self.push();
+    }
+}
+
+//==============================================================================
+//             SaikoRenderContextImageStyler Trait
+//==============================================================================
+
+pub struct SaikoRenderContextImageStyler<'r> {
+    buffer : &'r mut SaikoBuffer,
+    bounds : Bounds,
+    image : AssetId<Image>,
+    uv_rect : Rect,
+    tint : Color,
+    fit : ImageFit,
+    border_radius : Vec4,
+    is_pushed : bool,
+}
+
+impl <'r> SaikoRenderContextImageStyler<'r> {
+    
+    /// Only draws part of the image. The rect goes from (0, 0) at the top left of the image to
+    /// (1, 1) at the bottom right.
+    pub fn uv_rect(mut self, rect : Rect) -> Self {
+        self.uv_rect = rect;
+        self
+    }
+    
+    pub fn tint(mut self, color : impl Into<Color>) -> Self {
+        self.tint = color.into();
+        self
+    }
+    
+    pub fn fit(mut self, fit : ImageFit) -> Self {
+        self.fit = fit;
+        self
+    }
+    
+    pub fn border_radius(mut self, radius : impl Into<Vec4>) -> Self {
+        self.border_radius = radius.into();
+        self
+    }
+}
+
+impl <'r> SaikoRenderContextImageStyler<'r> {
+    /// Pushes the image to the buffer, if it has not been already.
+    fn push(&mut self) {
+        if self.is_pushed { return }
+        self.is_pushed = true;
+        
+        self.buffer.push_image(ImageBuffer {
+            bound : self.bounds,
+            image : self.image,
+            uv_rect : self.uv_rect,
+            tint : self.tint,
+            fit : self.fit,
+            border_radius : self.border_radius,
+            clip : 0,
         });
     }
-}
\ No newline at end of file
+}
+
+impl <'r> SaikoRenderContextExtention for SaikoRenderContextImageStyler<'r> {
+    fn get_bounds(&self) -> &Bounds {
+        &self.bounds
+    }
+    
+    /// The image is pushed before any of its children, so they are drawn on top of it.
+    fn get_buffer(&mut self) -> &mut SaikoBuffer {
+        self.push();
+        self.buffer
+    }
+}
+
+impl Drop for SaikoRenderContextImageStyler<'_> {
+    fn drop(&mut self) {
+        self.push();
+    }
+}
+
+//==============================================================================
+//             SaikoRenderContextEllipseStyler Trait
+//==============================================================================
+
+pub struct SaikoRenderContextEllipseStyler<'r> {
+    buffer : &'r mut SaikoBuffer,
+    bounds : Bounds,
+    ellipse : EllipseBuffer,
+    is_pushed : bool,
+}
+
+impl <'r> SaikoRenderContextEllipseStyler<'r> {
+    
+    pub fn color(mut self, color : impl Into<Color>) -> Self {
+        self.ellipse.fill_color = color.into();
+        self
+    }
+    
+    /// Outlines the shape, centered on its edge. This includes the inside of a ring and the
+    /// straight edges of an arc.
+    pub fn stroke(mut self, width : f32, color : impl Into<Color>) -> Self {
+        self.ellipse.stroke_width = width.max(0.0);
+        self.ellipse.stroke_color = color.into();
+        self
+    }
+    
+    /// Hollows out the middle of the shape, leaving a ring.
+    pub fn ring(mut self, inner_radius : f32) -> Self {
+        self.ellipse.inner_radius = inner_radius.max(0.0);
+        self
+    }
+    
+    /// Only draws the part of the shape between the two angles, in radians. An angle of 0 points
+    /// right, and the arc goes counter clockwise from `start_angle` to `end_angle`.
+    pub fn arc(mut self, start_angle : f32, end_angle : f32) -> Self {
+        self.ellipse.start_angle = start_angle;
+        self.ellipse.end_angle = end_angle;
+        self
+    }
+}
+
+impl <'r> SaikoRenderContextEllipseStyler<'r> {
+    /// Pushes the ellipse to the buffer, if it has not been already.
+    fn push(&mut self) {
+        if self.is_pushed { return }
+        self.is_pushed = true;
+        
+        self.buffer.push_ellipse(EllipseBuffer {
+            bound : self.bounds,
+            ..self.ellipse
+        });
+    }
+}
+
+impl <'r> SaikoRenderContextExtention for SaikoRenderContextEllipseStyler<'r> {
+    fn get_bounds(&self) -> &Bounds {
+        &self.bounds
+    }
+    
+    /// The ellipse is pushed before any of its children, so they are drawn on top of it.
+    fn get_buffer(&mut self) -> &mut SaikoBuffer {
+        self.push();
+        self.buffer
+    }
+}
+
+impl Drop for SaikoRenderContextEllipseStyler<'_> {
+    fn drop(&mut self) {
+        self.push();
+    }
+}
+
+//==============================================================================
+//             SaikoRenderContextPathStyler Trait
+//==============================================================================
+
+pub struct SaikoRenderContextPathStyler<'r> {
+    buffer : &'r mut SaikoBuffer,
+    bounds : Bounds,
+    outline : SaikoPath,
+    fill_color : Color,
+    fill_rule : FillRule,
+    stroke_color : Color,
+    stroke_width : f32,
+    line_cap : LineCap,
+    line_join : LineJoin,
+    is_pushed : bool,
+}
+
+impl <'r> SaikoRenderContextPathStyler<'r> {
+    
+    pub fn move_to(mut self, point : impl Into<Vec2>) -> Self {
+        self.outline.move_to(point);
+        self
+    }
+    
+    pub fn line_to(mut self, point : impl Into<Vec2>) -> Self {
+        self.outline.line_to(point);
+        self
+    }
+    
+    pub fn quad_to(mut self, control : impl Into<Vec2>, point : impl Into<Vec2>) -> Self {
+        self.outline.quad_to(control, point);
+        self
+    }
+    
+    pub fn cubic_to(mut self, control_1 : impl Into<Vec2>, control_2 : impl Into<Vec2>, point : impl Into<Vec2>) -> Self {
+        self.outline.cubic_to(control_1, control_2, point);
+        self
+    }
+    
+    pub fn close(mut self) -> Self {
+        self.outline.close();
+        self
+    }
+    
+    /// Fills the inside of the path. Open subpaths are filled as if they were closed.
+    pub fn fill(mut self, color : impl Into<Color>) -> Self {
+        self.fill_color = color.into();
+        self
+    }
+    
+    pub fn fill_rule(mut self, rule : FillRule) -> Self {
+        self.fill_rule = rule;
+        self
+    }
+    
+    /// Strokes the path. A width of 0 turns the stroke off.
+    pub fn stroke(mut self, width : f32, color : impl Into<Color>) -> Self {
+        self.stroke_width = width.max(0.0);
+        self.stroke_color = color.into();
+        self
+    }
+    
+    pub fn line_cap(mut self, cap : LineCap) -> Self {
+        self.line_cap = cap;
+        self
+    }
+    
+    pub fn line_join(mut self, join : LineJoin) -> Self {
+        self.line_join = join;
+        self
+    }
+}
+
+impl <'r> SaikoRenderContextPathStyler<'r> {
+    /// Pushes the path to the buffer, if it has not been already.
+    fn push(&mut self) {
+        if self.is_pushed { return }
+        self.is_pushed = true;
+        
+        let path = PathBuffer {
+            bound : self.bounds,
+            fill_color : self.fill_color,
+            stroke_color : self.stroke_color,
+            stroke_width : self.stroke_width,
+            fill_rule : self.fill_rule as u32,
+            ..Default::default()
+        };
+        
+        self.buffer.push_path(path, &self.outline, self.line_cap, self.line_join);
+    }
+}
+
+impl <'r> SaikoRenderContextExtention for SaikoRenderContextPathStyler<'r> {
+    fn get_bounds(&self) -> &Bounds {
+        &self.bounds
+    }
+    
+    /// The path is pushed before any of its children, so they are drawn on top of it.
+    fn get_buffer(&mut self) -> &mut SaikoBuffer {
+        self.push();
+        self.buffer
+    }
+}
+
+impl Drop for SaikoRenderContextPathStyler<'_> {
+    fn drop(&mut self) {
+        self.push();
+    }
+}
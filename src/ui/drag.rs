@@ -0,0 +1,335 @@
+//==============================================================================
+//  Drag and drop is built on top of the pointers. Pressing a Draggable node
+//  and moving the pointer past its threshold picks it up, and a ghost of the
+//  node follows the pointer until it is released. The ghost is drawn by the
+//  node's own components, so it looks exactly like what is being dragged.
+//  Only DropTargets that accept the kind of the payload can be dropped on.
+//==============================================================================
+
+use std::{any::Any, sync::Arc};
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{common::util::find_ancestor, render::SaikoRenderState};
+
+use super::{
+    interaction::{update_pointers, SaikoPointerDown, SaikoPointerId, SaikoPointers},
+    node::{SaikoNode, SaikoRoots},
+};
+
+/// The ghost is drawn over the rest of the ui.
+pub(crate) const DRAG_GHOST_Z_INDEX : i32 = 1 << 20;
+
+/// How opaque the ghost is, so what is under it can still be seen.
+pub(crate) const DRAG_GHOST_OPACITY : f32 = 0.75;
+
+//==============================================================================
+//          SaikoDragPlugin
+//==============================================================================
+
+pub(crate) struct SaikoDragPlugin;
+
+impl Plugin for SaikoDragPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<SaikoDragState>()
+            .add_event::<SaikoDragStart>()
+            .add_event::<SaikoDragOver>()
+            .add_event::<SaikoDrop>()
+            .add_event::<SaikoDragCancel>()
+            .add_systems(PreUpdate, update_drag.after(update_pointers))
+        ;
+    }
+}
+
+//==============================================================================
+//          Drag Components
+//==============================================================================
+
+/// Lets the node be dragged with the left button or a touch. The node needs a
+/// [`SaikoInteraction`](super::interaction::SaikoInteraction) so it can be pressed. The kind of
+/// the payload decides which drop targets accept it.
+#[derive(Component, Clone)]
+pub struct Draggable {
+    kind : String,
+    payload : Option<Arc<dyn Any + Send + Sync>>,
+    threshold : f32,
+}
+
+impl Draggable {
+    pub fn new(kind : impl Into<String>) -> Self {
+        Self { kind : kind.into(), payload : None, threshold : 4.0 }
+    }
+
+    pub fn with_payload<T : Any + Send + Sync>(mut self, payload : T) -> Self {
+        self.payload = Some(Arc::new(payload));
+        self
+    }
+
+    /// How many pixels the pointer has to move while pressed before the drag starts, so clicks
+    /// that move a little are still clicks.
+    pub fn with_threshold(mut self, threshold : f32) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    /// The payload, if it is a `T`.
+    pub fn payload<T : Any>(&self) -> Option<&T> {
+        self.payload.as_ref()?.downcast_ref()
+    }
+}
+
+/// Lets payloads of the accepted kinds be dropped on the node. The node, or a node under it,
+/// needs a [`SaikoInteraction`](super::interaction::SaikoInteraction) so the pointer can find it.
+#[derive(Component, Clone, Default)]
+pub struct DropTarget {
+    accepts : Vec<String>,
+    state : SaikoDropState,
+}
+
+impl DropTarget {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn accept(mut self, kind : impl Into<String>) -> Self {
+        self.accepts.push(kind.into());
+        self
+    }
+
+    pub fn accepts(&self, kind : &str) -> bool {
+        self.accepts.iter().any(|accepted| accepted == kind)
+    }
+
+    /// Whether something is being dragged over the target, and if it would be accepted.
+    pub fn state(&self) -> SaikoDropState {
+        self.state
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum SaikoDropState {
+    #[default]
+    None,
+    Accepting,
+    Rejecting,
+}
+
+//==============================================================================
+//          SaikoDragState
+//==============================================================================
+
+/// The node that is being dragged, if any.
+#[derive(Resource, Default)]
+pub struct SaikoDragState {
+    pending : Option<SaikoPendingDrag>,
+    drag : Option<SaikoDrag>,
+}
+
+impl SaikoDragState {
+    pub fn dragging(&self) -> Option<Entity> {
+        self.drag.as_ref().map(|drag| drag.entity)
+    }
+
+    pub fn target(&self) -> Option<Entity> {
+        self.drag.as_ref()?.target
+    }
+
+    pub(crate) fn ghost(&self) -> Option<&SaikoDrag> {
+        self.drag.as_ref()
+    }
+}
+
+/// A Draggable node that was pressed, but has not been moved past its threshold yet.
+struct SaikoPendingDrag {
+    entity : Entity,
+    pointer : SaikoPointerId,
+    start : Vec2,
+}
+
+pub(crate) struct SaikoDrag {
+    pub(crate) entity : Entity,
+    pub(crate) window : Entity,
+    /// Where the pointer is, in the ui of the window.
+    pub(crate) position : Vec2,
+    /// From the pointer to the center of the node, so the ghost keeps the spot it was grabbed at.
+    pub(crate) offset : Vec2,
+    pointer : SaikoPointerId,
+    target : Option<Entity>,
+    accepted : bool,
+}
+
+//==============================================================================
+//          SaikoDrag Events
+//==============================================================================
+
+#[derive(Event, Clone, Debug)]
+pub struct SaikoDragStart {
+    pub entity : Entity,
+    pub pointer : SaikoPointerId,
+}
+
+/// The dragged node moved over a new drop target.
+#[derive(Event, Clone, Debug)]
+pub struct SaikoDragOver {
+    pub entity : Entity,
+    pub target : Entity,
+    pub accepted : bool,
+}
+
+/// The dragged node was released over a drop target that accepts it.
+#[derive(Event, Clone, Debug)]
+pub struct SaikoDrop {
+    pub entity : Entity,
+    pub target : Entity,
+}
+
+/// The dragged node was released somewhere that does not accept it, or Escape was pressed.
+#[derive(Event, Clone, Debug)]
+pub struct SaikoDragCancel {
+    pub entity : Entity,
+}
+
+#[derive(SystemParam)]
+struct SaikoDragEvents<'w> {
+    start : EventWriter<'w, SaikoDragStart>,
+    over : EventWriter<'w, SaikoDragOver>,
+    drop : EventWriter<'w, SaikoDrop>,
+    cancel : EventWriter<'w, SaikoDragCancel>,
+}
+
+//==============================================================================
+//          SaikoDrag Systems
+//==============================================================================
+
+fn update_drag(
+    mut state : ResMut<SaikoDragState>,
+    mut render_state : ResMut<SaikoRenderState>,
+    mut events : SaikoDragEvents,
+    mut pointer_down : EventReader<SaikoPointerDown>,
+    mut drop_targets : Query<&mut DropTarget>,
+    pointers : Res<SaikoPointers>,
+    keys : Res<ButtonInput<KeyCode>>,
+    draggables : Query<&Draggable>,
+    nodes : Query<&SaikoNode>,
+    parents : Query<&Parent>,
+    roots : SaikoRoots,
+) {
+    let state = &mut *state;
+
+    for event in pointer_down.read() {
+        if event.button != MouseButton::Left || state.drag.is_some() { continue }
+        let Some(entity) = find_ancestor(event.entity, &parents, |entity| draggables.contains(entity)) else { continue };
+        state.pending = Some(SaikoPendingDrag { entity, pointer : event.pointer, start : event.position });
+    }
+
+    // Where the pointer is while it is still held down.
+    let held = |pointer : SaikoPointerId| {
+        pointers
+            .get(pointer)
+            .filter(|pointer| pointer.pressed.contains_key(&MouseButton::Left))
+            .map(|pointer| pointer.location)
+    };
+
+    if let Some(pending) = &state.pending {
+        match held(pending.pointer) {
+            None => state.pending = None,
+            Some(Some((window, position))) => {
+                let threshold = draggables.get(pending.entity).map_or(0.0, |draggable| draggable.threshold);
+                if position.distance(pending.start) > threshold {
+                    let center = nodes.get(pending.entity).map_or(pending.start, |node| node.bounds().center);
+                    events.start.send(SaikoDragStart { entity : pending.entity, pointer : pending.pointer });
+                    render_state.mark_window_dirty(window);
+                    state.drag = Some(SaikoDrag {
+                        entity : pending.entity,
+                        window,
+                        position,
+                        offset : center - pending.start,
+                        pointer : pending.pointer,
+                        target : None,
+                        accepted : false,
+                    });
+                    state.pending = None;
+                }
+            },
+            Some(None) => {},
+        }
+    }
+
+    let Some(drag) = &mut state.drag else { return };
+    let Ok(draggable) = draggables.get(drag.entity) else {
+        finish_drag(state, &mut drop_targets, &mut render_state, &mut events, false);
+        return;
+    };
+
+    let location = match held(drag.pointer) {
+        Some(location) if !keys.just_pressed(KeyCode::Escape) => location,
+        released => {
+            let dropped = released.is_none();
+            finish_drag(state, &mut drop_targets, &mut render_state, &mut events, dropped);
+            return;
+        },
+    };
+
+    // The ghost follows the pointer, which redraws the window it is in.
+    if let Some((window, position)) = location {
+        if drag.window != window || drag.position != position {
+            render_state.mark_window_dirty(drag.window);
+            render_state.mark_window_dirty(window);
+            drag.window = window;
+            drag.position = position;
+        }
+    }
+
+    let hovered = pointers.get(drag.pointer).and_then(|pointer| pointer.hovered);
+    let target = hovered
+        .and_then(|hovered| find_ancestor(hovered, &parents, |entity| drop_targets.contains(entity)))
+        .filter(|target| roots.window(*target) == Some(drag.window));
+    if target == drag.target { return }
+
+    set_drop_state(&mut drop_targets, drag.target, SaikoDropState::None);
+    drag.target = target;
+    drag.accepted = false;
+
+    let Some(target) = target else { return };
+    drag.accepted = drop_targets.get(target).is_ok_and(|drop_target| drop_target.accepts(draggable.kind()));
+    let drop_state = if drag.accepted { SaikoDropState::Accepting } else { SaikoDropState::Rejecting };
+    set_drop_state(&mut drop_targets, Some(target), drop_state);
+    events.over.send(SaikoDragOver { entity : drag.entity, target, accepted : drag.accepted });
+}
+
+/// Ends the drag, dropping the node on its target if the pointer was released over one that
+/// accepts it, and cancelling it otherwise.
+fn finish_drag(
+    state : &mut SaikoDragState,
+    drop_targets : &mut Query<&mut DropTarget>,
+    render_state : &mut SaikoRenderState,
+    events : &mut SaikoDragEvents,
+    dropped : bool,
+) {
+    let Some(drag) = state.drag.take() else { return };
+    set_drop_state(drop_targets, drag.target, SaikoDropState::None);
+    render_state.mark_window_dirty(drag.window);
+
+    match drag.target {
+        Some(target) if dropped && drag.accepted => {
+            events.drop.send(SaikoDrop { entity : drag.entity, target });
+        },
+        _ => {
+            events.cancel.send(SaikoDragCancel { entity : drag.entity });
+        },
+    }
+}
+
+/// Only writes the state when it changes, so targets that restyle on change are not redrawn
+/// every frame.
+fn set_drop_state(drop_targets : &mut Query<&mut DropTarget>, target : Option<Entity>, drop_state : SaikoDropState) {
+    let Some(mut drop_target) = target.and_then(|target| drop_targets.get_mut(target).ok()) else { return };
+    if drop_target.state != drop_state {
+        drop_target.state = drop_state;
+    }
+}
@@ -0,0 +1,429 @@
+//==============================================================================
+//  Flex lays out the children of a node in rows or columns, like css
+//  flexbox. The container is the parent node, and only the children with a
+//  SaikoFlexItem take part, so other children can still be aligned by hand.
+//  Sizes are measured from edge to edge like in css, so a basis or gap of 10
+//  pixels covers 10 pixels on screen, and percentages are of the container.
+//==============================================================================
+
+use bevy::prelude::*;
+
+use crate::common::{bounds::Bounds, value::Value};
+
+//==============================================================================
+//          SaikoFlexContainer
+//==============================================================================
+
+#[derive(Reflect, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum SaikoFlexDirection {
+    /// Left to right.
+    #[default]
+    Row,
+    /// Top to bottom.
+    Column,
+    RowReverse,
+    ColumnReverse,
+}
+
+#[derive(Reflect, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum SaikoFlexWrap {
+    #[default]
+    NoWrap,
+    /// Items that don't fit start a new line after the last one.
+    Wrap,
+    /// Items that don't fit start a new line before the last one.
+    WrapReverse,
+}
+
+/// Where the items of a line go along the main axis, when they don't fill it.
+#[derive(Reflect, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum SaikoJustify {
+    #[default]
+    Start,
+    End,
+    Center,
+    SpaceBetween,
+    SpaceAround,
+    SpaceEvenly,
+}
+
+/// Where an item goes across its line.
+#[derive(Reflect, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum SaikoAlign {
+    Start,
+    End,
+    Center,
+    /// Items without a cross size fill their line, and the rest go to the start.
+    #[default]
+    Stretch,
+}
+
+/// Lays out the children of this node that have a [`SaikoFlexItem`].
+#[derive(Component, Reflect, Clone, Copy, Default)]
+#[reflect(Component)]
+pub struct SaikoFlexContainer {
+    pub direction : SaikoFlexDirection,
+    pub wrap : SaikoFlexWrap,
+    pub justify : SaikoJustify,
+    pub align_items : SaikoAlign,
+    /// The space between items, and between lines when they wrap.
+    pub gap : Value,
+}
+
+impl SaikoFlexContainer {
+    pub fn row() -> Self {
+        Self::default()
+    }
+
+    pub fn column() -> Self {
+        Self { direction : SaikoFlexDirection::Column, ..Default::default() }
+    }
+
+    pub fn with_direction(mut self, direction : SaikoFlexDirection) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    pub fn with_wrap(mut self, wrap : SaikoFlexWrap) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    pub fn with_justify(mut self, justify : SaikoJustify) -> Self {
+        self.justify = justify;
+        self
+    }
+
+    pub fn with_align_items(mut self, align_items : SaikoAlign) -> Self {
+        self.align_items = align_items;
+        self
+    }
+
+    pub fn with_gap(mut self, gap : impl Into<Value>) -> Self {
+        self.gap = gap.into();
+        self
+    }
+}
+
+//==============================================================================
+//          SaikoFlexItem
+//==============================================================================
+
+/// Places this node in the flex layout of its parent.
+#[derive(Component, Reflect, Clone, Copy)]
+#[reflect(Component)]
+pub struct SaikoFlexItem {
+    /// How much of the leftover space in the line this item takes, compared to the others.
+    pub grow : f32,
+    /// How much this item shrinks when the line overflows, compared to the others.
+    pub shrink : f32,
+    /// The size along the main axis, before growing or shrinking.
+    pub basis : Value,
+    /// The size across the line. Items without one are stretched to fill their line.
+    pub cross_size : Option<Value>,
+    /// Overrides the `align_items` of the container for this item.
+    pub align_self : Option<SaikoAlign>,
+}
+
+impl Default for SaikoFlexItem {
+    fn default() -> Self {
+        Self {
+            grow : 0.0,
+            shrink : 1.0,
+            basis : Value::default(),
+            cross_size : None,
+            align_self : None,
+        }
+    }
+}
+
+impl SaikoFlexItem {
+    pub fn new(basis : impl Into<Value>) -> Self {
+        Self { basis : basis.into(), ..Default::default() }
+    }
+
+    pub fn with_grow(mut self, grow : f32) -> Self {
+        self.grow = grow;
+        self
+    }
+
+    pub fn with_shrink(mut self, shrink : f32) -> Self {
+        self.shrink = shrink;
+        self
+    }
+
+    pub fn with_cross_size(mut self, cross_size : impl Into<Value>) -> Self {
+        self.cross_size = Some(cross_size.into());
+        self
+    }
+
+    pub fn with_align_self(mut self, align : SaikoAlign) -> Self {
+        self.align_self = Some(align);
+        self
+    }
+}
+
+//==============================================================================
+//          Flex Layout
+//==============================================================================
+
+/// The bounds of every item inside of the container bounds, in the same order as the items.
+pub(crate) fn layout_flex(container : &SaikoFlexContainer, bounds : &Bounds, items : &[SaikoFlexItem]) -> Vec<Bounds> {
+    if items.is_empty() { return vec![] }
+
+    let is_row = matches!(container.direction, SaikoFlexDirection::Row | SaikoFlexDirection::RowReverse);
+    let (main_axis, cross_axis) = if is_row { (0, 1) } else { (1, 0) };
+    // The layout is measured from edge to edge, and the size of bounds is half of that.
+    let main_length = bounds.size[main_axis] * 2.0;
+    let cross_length = bounds.size[cross_axis] * 2.0;
+    let main_gap = container.gap.to_pixels(main_length);
+    let cross_gap = container.gap.to_pixels(cross_length);
+
+    let bases = items
+        .iter()
+        .map(|item| item.basis.to_pixels(main_length).max(0.0))
+        .collect::<Vec<_>>();
+
+    // Items are added to a line until the next one would overflow it.
+    let mut lines : Vec<Vec<usize>> = vec![];
+    let mut line_length = 0.0;
+    for (index, basis) in bases.iter().enumerate() {
+        let wraps = container.wrap != SaikoFlexWrap::NoWrap;
+        match lines.last_mut() {
+            Some(line) if !wraps || line_length + main_gap + basis <= main_length => {
+                line.push(index);
+                line_length += main_gap + basis;
+            },
+            _ => {
+                lines.push(vec![index]);
+                line_length = *basis;
+            },
+        }
+    }
+    if container.wrap == SaikoFlexWrap::WrapReverse {
+        lines.reverse();
+    }
+
+    // A single line fills the container, and wrapped lines are as big as their biggest item.
+    let fixed_cross = |index : usize| items[index].cross_size.map(|size| size.to_pixels(cross_length));
+    let line_crosses = match lines.len() {
+        1 => vec![cross_length],
+        count => {
+            let share = ((cross_length - cross_gap * (count - 1) as f32) / count as f32).max(0.0);
+            lines
+                .iter()
+                .map(|line| line.iter().filter_map(|index| fixed_cross(*index)).reduce(f32::max).unwrap_or(share))
+                .collect()
+        },
+    };
+
+    let mut item_bounds = vec![Bounds::default(); items.len()];
+    let mut cross_position = 0.0;
+    for (line, line_cross) in lines.iter().zip(line_crosses) {
+        let sizes = flex_line(line, &bases, items, main_length, main_gap);
+        let used = sizes.iter().sum::<f32>() + main_gap * (line.len() - 1) as f32;
+        let (mut main_position, spacing) = justify(container.justify, (main_length - used).max(0.0), line.len());
+
+        for (index, main_size) in line.iter().zip(sizes) {
+            let item = &items[*index];
+            let align = item.align_self.unwrap_or(container.align_items);
+            let cross_size = fixed_cross(*index).unwrap_or(line_cross);
+            let cross_offset = match align {
+                SaikoAlign::Start | SaikoAlign::Stretch => 0.0,
+                SaikoAlign::End => line_cross - cross_size,
+                SaikoAlign::Center => (line_cross - cross_size) * 0.5,
+            };
+
+            let mut size = Vec2::ZERO;
+            size[main_axis] = main_size;
+            size[cross_axis] = cross_size;
+
+            let center = Vec2::new(main_position + main_size * 0.5, cross_position + cross_offset + cross_size * 0.5);
+            item_bounds[*index] = place(container.direction, bounds, center, size);
+            main_position += main_size + main_gap + spacing;
+        }
+
+        cross_position += line_cross + cross_gap;
+    }
+
+    item_bounds
+}
+
+/// The main size of every item in the line, after growing into the leftover space or shrinking
+/// to fit.
+fn flex_line(line : &[usize], bases : &[f32], items : &[SaikoFlexItem], main_length : f32, main_gap : f32) -> Vec<f32> {
+    let used = line.iter().map(|index| bases[*index]).sum::<f32>() + main_gap * (line.len() - 1) as f32;
+    let free = main_length - used;
+    let total_grow = line.iter().map(|index| items[*index].grow.max(0.0)).sum::<f32>();
+    // Bigger items shrink more, so small items don't disappear first.
+    let total_shrink = line.iter().map(|index| items[*index].shrink.max(0.0) * bases[*index]).sum::<f32>();
+
+    line
+        .iter()
+        .map(|index| {
+            let basis = bases[*index];
+            let item = &items[*index];
+            match free {
+                free if free > 0.0 && total_grow > 0.0 => basis + free * item.grow.max(0.0) / total_grow,
+                free if free < 0.0 && total_shrink > 0.0 => (basis + free * item.shrink.max(0.0) * basis / total_shrink).max(0.0),
+                _ => basis,
+            }
+        })
+        .collect()
+}
+
+/// Where the first item of a line starts, and the extra space between each item.
+fn justify(justify : SaikoJustify, free : f32, count : usize) -> (f32, f32) {
+    let count = count as f32;
+    match justify {
+        SaikoJustify::Start => (0.0, 0.0),
+        SaikoJustify::End => (free, 0.0),
+        SaikoJustify::Center => (free * 0.5, 0.0),
+        SaikoJustify::SpaceBetween if count > 1.0 => (0.0, free / (count - 1.0)),
+        SaikoJustify::SpaceBetween => (0.0, 0.0),
+        SaikoJustify::SpaceAround => (free / count * 0.5, free / count),
+        SaikoJustify::SpaceEvenly => (free / (count + 1.0), free / (count + 1.0)),
+    }
+}
+
+/// Turns the center of an item along the main and cross axis into bounds inside of the container.
+/// Rows start at the left and columns at the top, and lines are stacked from the top or the left.
+/// The size is from edge to edge, so it is halved for the bounds.
+fn place(direction : SaikoFlexDirection, bounds : &Bounds, center : Vec2, size : Vec2) -> Bounds {
+    let left = bounds.center.x - bounds.size.x;
+    let right = bounds.center.x + bounds.size.x;
+    let top = bounds.center.y + bounds.size.y;
+    let bottom = bounds.center.y - bounds.size.y;
+    let (main, cross) = (center.x, center.y);
+
+    let center = match direction {
+        SaikoFlexDirection::Row => Vec2::new(left + main, top - cross),
+        SaikoFlexDirection::RowReverse => Vec2::new(right - main, top - cross),
+        SaikoFlexDirection::Column => Vec2::new(left + cross, top - main),
+        SaikoFlexDirection::ColumnReverse => Vec2::new(left + cross, bottom + main),
+    };
+    Bounds::new(center, size * 0.5, bounds.z_index)
+}
+
+//==============================================================================
+//          Tests
+//==============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A container that is 100 by 20 pixels on screen, centered on the origin.
+    fn layout(container : SaikoFlexContainer, items : &[SaikoFlexItem]) -> Vec<Bounds> {
+        layout_flex(&container, &Bounds::new(Vec2::ZERO, Vec2::new(50.0, 10.0), 0), items)
+    }
+
+    fn centers(bounds : &[Bounds]) -> Vec<Vec2> {
+        bounds.iter().map(|bounds| bounds.center).collect()
+    }
+
+    /// The widths of the items on screen.
+    fn widths(bounds : &[Bounds]) -> Vec<f32> {
+        bounds.iter().map(|bounds| bounds.size.x * 2.0).collect()
+    }
+
+    #[test]
+    fn sizes_are_edge_to_edge() {
+        let bounds = layout(SaikoFlexContainer::row().with_gap(5.0), &[SaikoFlexItem::new(10.0), SaikoFlexItem::new(10.0)]);
+
+        // The first item covers -50 to -40, and the gap leaves 5 pixels before the second item.
+        assert_eq!(centers(&bounds), vec![Vec2::new(-45.0, 0.0), Vec2::new(-30.0, 0.0)]);
+        assert_eq!(bounds[0].size, Vec2::new(5.0, 10.0));
+        assert_eq!(widths(&bounds), vec![10.0, 10.0]);
+    }
+
+    #[test]
+    fn grow_and_shrink() {
+        let bounds = layout(SaikoFlexContainer::row(), &[
+            SaikoFlexItem::new(10.0).with_grow(1.0),
+            SaikoFlexItem::new(10.0).with_grow(3.0),
+        ]);
+        assert_eq!(widths(&bounds), vec![30.0, 70.0]);
+        assert_eq!(centers(&bounds), vec![Vec2::new(-35.0, 0.0), Vec2::new(15.0, 0.0)]);
+
+        // Items shrink by their basis, and items that don't shrink keep their size.
+        let bounds = layout(SaikoFlexContainer::row(), &[
+            SaikoFlexItem::new(40.0),
+            SaikoFlexItem::new(40.0),
+            SaikoFlexItem::new(40.0).with_shrink(0.0),
+        ]);
+        assert_eq!(widths(&bounds), vec![30.0, 30.0, 40.0]);
+    }
+
+    #[test]
+    fn wrap_and_wrap_reverse() {
+        let items = [SaikoFlexItem::new(40.0), SaikoFlexItem::new(40.0), SaikoFlexItem::new(40.0)];
+
+        let bounds = layout(SaikoFlexContainer::row().with_wrap(SaikoFlexWrap::Wrap), &items);
+        assert_eq!(centers(&bounds), vec![Vec2::new(-30.0, 5.0), Vec2::new(10.0, 5.0), Vec2::new(-30.0, -5.0)]);
+        assert_eq!(bounds[2].size, Vec2::new(20.0, 5.0));
+
+        let bounds = layout(SaikoFlexContainer::row().with_wrap(SaikoFlexWrap::WrapReverse), &items);
+        assert_eq!(centers(&bounds), vec![Vec2::new(-30.0, -5.0), Vec2::new(10.0, -5.0), Vec2::new(-30.0, 5.0)]);
+
+        // Without wrapping the items shrink to fit on one line.
+        let bounds = layout(SaikoFlexContainer::row(), &items);
+        assert!(bounds.iter().all(|bounds| bounds.center.y == 0.0));
+    }
+
+    #[test]
+    fn justify() {
+        let items = [SaikoFlexItem::new(20.0), SaikoFlexItem::new(20.0)];
+        let cases = [
+            (SaikoJustify::Start, [-40.0, -20.0]),
+            (SaikoJustify::End, [20.0, 40.0]),
+            (SaikoJustify::Center, [-10.0, 10.0]),
+            (SaikoJustify::SpaceBetween, [-40.0, 40.0]),
+            (SaikoJustify::SpaceAround, [-25.0, 25.0]),
+            (SaikoJustify::SpaceEvenly, [-20.0, 20.0]),
+        ];
+
+        for (justify, expected) in cases {
+            let bounds = layout(SaikoFlexContainer::row().with_justify(justify), &items);
+            let x = bounds.iter().map(|bounds| bounds.center.x).collect::<Vec<_>>();
+            assert_eq!(x, expected, "{justify:?}");
+        }
+    }
+
+    #[test]
+    fn directions() {
+        let row_items = [SaikoFlexItem::new(20.0), SaikoFlexItem::new(20.0)];
+        let bounds = layout(SaikoFlexContainer::row().with_direction(SaikoFlexDirection::RowReverse), &row_items);
+        assert_eq!(centers(&bounds), vec![Vec2::new(40.0, 0.0), Vec2::new(20.0, 0.0)]);
+
+        // Columns go down from the top, and stretch across the whole width.
+        let column_items = [SaikoFlexItem::new(4.0), SaikoFlexItem::new(6.0)];
+        let bounds = layout(SaikoFlexContainer::column(), &column_items);
+        assert_eq!(centers(&bounds), vec![Vec2::new(0.0, 8.0), Vec2::new(0.0, 3.0)]);
+        assert_eq!(bounds[1].size, Vec2::new(50.0, 3.0));
+
+        let bounds = layout(SaikoFlexContainer::column().with_direction(SaikoFlexDirection::ColumnReverse), &column_items);
+        assert_eq!(centers(&bounds), vec![Vec2::new(0.0, -8.0), Vec2::new(0.0, -3.0)]);
+    }
+
+    #[test]
+    fn align_items() {
+        let item = SaikoFlexItem::new(20.0).with_cross_size(8.0);
+        let cases = [
+            (SaikoAlign::Start, 6.0),
+            (SaikoAlign::Stretch, 6.0),
+            (SaikoAlign::Center, 0.0),
+            (SaikoAlign::End, -6.0),
+        ];
+
+        for (align, expected) in cases {
+            let bounds = layout(SaikoFlexContainer::row().with_align_items(align), &[item]);
+            assert_eq!(bounds[0].center.y, expected, "{align:?}");
+        }
+    }
+
+    #[test]
+    fn no_items() {
+        assert!(layout(SaikoFlexContainer::row().with_wrap(SaikoFlexWrap::Wrap), &[]).is_empty());
+    }
+}
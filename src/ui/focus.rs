@@ -0,0 +1,234 @@
+//==============================================================================
+//  Focus decides which node gets the keyboard. Tab and Shift-Tab move the
+//  focus through every Focusable node in tree order, and pressing a node
+//  with a pointer focuses it. Focus rings are only shown once the keyboard
+//  has been used, so clicking a button does not outline it.
+//==============================================================================
+
+use bevy::{input::InputSystem, prelude::*};
+
+use crate::{common::util::{find_ancestor, get_all_children}, render::SaikoRenderState};
+
+use super::{interaction::{update_pointers, SaikoPointerDown}, node::{SaikoNode, SaikoRoots}};
+
+//==============================================================================
+//          SaikoFocusPlugin
+//==============================================================================
+
+pub(crate) struct SaikoFocusPlugin;
+
+impl Plugin for SaikoFocusPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<SaikoFocus>()
+            .add_event::<SaikoFocusIn>()
+            .add_event::<SaikoFocusOut>()
+            .add_event::<SaikoFocusSubmit>()
+            .add_systems(PreUpdate, (focus_on_pointer_down, navigate_focus).chain().after(InputSystem).after(update_pointers))
+            .add_systems(PostUpdate, send_focus_events)
+
+            .register_type::<Focusable>()
+        ;
+    }
+}
+
+//==============================================================================
+//          Focusable Component
+//==============================================================================
+
+/// Lets the node be focused. Nodes with a positive tab index are visited first, from the lowest
+/// index up, then every node with a tab index of 0 in tree order. Nodes with a negative tab index
+/// can only be focused with a pointer or with [`SaikoFocus::focus`].
+#[derive(Component, Reflect, Clone, Copy, Default)]
+#[reflect(Component)]
+pub struct Focusable {
+    pub tab_index : i32,
+}
+
+impl Focusable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_tab_index(mut self, tab_index : i32) -> Self {
+        self.tab_index = tab_index;
+        self
+    }
+}
+
+//==============================================================================
+//          SaikoFocus
+//==============================================================================
+
+/// The node that has keyboard focus.
+#[derive(Resource, Default)]
+pub struct SaikoFocus {
+    focused : Option<Entity>,
+    is_visible : bool,
+}
+
+impl SaikoFocus {
+    pub fn focused(&self) -> Option<Entity> {
+        self.focused
+    }
+
+    /// Whether the focus ring of the focused node is shown. It is hidden while a pointer is
+    /// used, and shown again when the keyboard is used.
+    pub fn is_visible(&self) -> bool {
+        self.is_visible
+    }
+
+    pub fn focus(&mut self, entity : Entity) {
+        self.focused = Some(entity);
+        self.is_visible = true;
+    }
+
+    pub fn clear(&mut self) {
+        self.focused = None;
+    }
+}
+
+//==============================================================================
+//          SaikoFocus Events
+//==============================================================================
+
+/// The node gained focus.
+#[derive(Event, Clone, Debug)]
+pub struct SaikoFocusIn {
+    pub entity : Entity,
+}
+
+/// The node lost focus.
+#[derive(Event, Clone, Debug)]
+pub struct SaikoFocusOut {
+    pub entity : Entity,
+}
+
+/// Enter or Space was pressed while the node was focused. This is how a focused node is clicked
+/// without a mouse.
+#[derive(Event, Clone, Debug)]
+pub struct SaikoFocusSubmit {
+    pub entity : Entity,
+}
+
+//==============================================================================
+//          SaikoFocus Systems
+//==============================================================================
+
+/// Pressing a node focuses the closest Focusable node it is in, and pressing anything else clears
+/// the focus.
+fn focus_on_pointer_down(
+    mut focus : ResMut<SaikoFocus>,
+    mut pointer_down : EventReader<SaikoPointerDown>,
+    focusables : Query<(), With<Focusable>>,
+    parents : Query<&Parent>,
+) {
+    for event in pointer_down.read() {
+        focus.focused = find_ancestor(event.entity, &parents, |entity| focusables.contains(entity));
+        focus.is_visible = false;
+    }
+}
+
+pub(crate) fn navigate_focus(
+    mut focus : ResMut<SaikoFocus>,
+    mut submit : EventWriter<SaikoFocusSubmit>,
+    keys : Res<ButtonInput<KeyCode>>,
+    mut hierarchy : Query<Option<&Children>>,
+    nodes : Query<(Entity, &Focusable, Option<&InheritedVisibility>), With<SaikoNode>>,
+    node_parents : Query<&Parent, With<SaikoNode>>,
+) {
+    // The focused node was despawned, or can no longer be focused.
+    if focus.focused.is_some_and(|focused| !nodes.contains(focused)) {
+        focus.clear();
+    }
+
+    if let Some(entity) = focus.focused {
+        if keys.any_just_pressed([KeyCode::Enter, KeyCode::NumpadEnter, KeyCode::Space]) {
+            submit.send(SaikoFocusSubmit { entity });
+            focus.is_visible = true;
+        }
+    }
+
+    if keys.just_pressed(KeyCode::Escape) {
+        focus.clear();
+    }
+
+    if !keys.just_pressed(KeyCode::Tab) { return }
+
+    // The roots are sorted so the order of separate trees does not change between frames.
+    let mut roots = nodes
+        .iter()
+        .map(|(entity, _, _)| find_root(entity, &node_parents))
+        .collect::<Vec<_>>();
+    roots.sort();
+    roots.dedup();
+
+    let mut order = roots
+        .into_iter()
+        .flat_map(|root| get_all_children(root, &mut hierarchy))
+        .filter_map(|entity| nodes.get(entity).ok())
+        .filter(|(_, focusable, visibility)| focusable.tab_index >= 0 && visibility.map_or(true, |v| v.get()))
+        .map(|(entity, focusable, _)| (entity, focusable.tab_index))
+        .collect::<Vec<_>>();
+    if order.is_empty() { return }
+
+    // The sort is stable, so nodes with the same tab index stay in tree order.
+    order.sort_by_key(|(_, tab_index)| match tab_index {
+        0 => i32::MAX,
+        tab_index => *tab_index,
+    });
+
+    let current = focus.focused.and_then(|focused| order.iter().position(|(entity, _)| *entity == focused));
+    let backwards = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let next = match (current, backwards) {
+        (None, false) => 0,
+        (None, true) => order.len() - 1,
+        (Some(index), false) => (index + 1) % order.len(),
+        (Some(index), true) => (index + order.len() - 1) % order.len(),
+    };
+
+    focus.focus(order[next].0);
+}
+
+/// The topmost node above the entity, following only parents that are nodes.
+fn find_root(entity : Entity, parents : &Query<&Parent, With<SaikoNode>>) -> Entity {
+    let mut current_entity = entity;
+    while let Ok(parent) = parents.get(current_entity) {
+        current_entity = **parent;
+    }
+    current_entity
+}
+
+/// Sends the focus events for every change to the focus this frame, however it was made. The
+/// windows of the nodes that gain and lose focus are redrawn, so their focus rings are shown or
+/// hidden without laying the nodes out again.
+fn send_focus_events(
+    focus : Res<SaikoFocus>,
+    mut last_focus : Local<(Option<Entity>, bool)>,
+    mut focus_in : EventWriter<SaikoFocusIn>,
+    mut focus_out : EventWriter<SaikoFocusOut>,
+    mut render_state : ResMut<SaikoRenderState>,
+    roots : SaikoRoots,
+) {
+    let (last_focused, last_visible) = *last_focus;
+    if last_focused == focus.focused && last_visible == focus.is_visible { return }
+    *last_focus = (focus.focused, focus.is_visible);
+
+    if last_focused != focus.focused {
+        if let Some(entity) = last_focused {
+            focus_out.send(SaikoFocusOut { entity });
+        }
+        if let Some(entity) = focus.focused {
+            focus_in.send(SaikoFocusIn { entity });
+        }
+    }
+
+    // Nodes that are not in a window are in an image target, which is only redrawn with the rest
+    // of the ui.
+    for entity in [last_focused, focus.focused].into_iter().flatten() {
+        match roots.window(entity) {
+            Some(window) => render_state.mark_window_dirty(window),
+            None => render_state.mark_dirty(),
+        }
+    }
+}
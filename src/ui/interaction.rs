@@ -0,0 +1,327 @@
+//==============================================================================
+//  Interaction maps the mouse and touches to the topmost SaikoNode under
+//  them, using the bounds that were laid out last frame. Only nodes with a
+//  SaikoInteraction component can be hit, so containers never block the
+//  nodes behind them. Every change is sent as an event to the node, and the
+//  SaikoInteraction component keeps the current state for restyling.
+//==============================================================================
+
+use bevy::{
+    ecs::system::SystemParam,
+    input::{mouse::{MouseScrollUnit, MouseWheel}, touch::Touches, InputSystem},
+    prelude::*,
+    utils::HashMap,
+};
+
+use crate::common::bounds::Bounds;
+
+use super::node::{SaikoNode, SaikoRoots};
+
+//==============================================================================
+//          SaikoInteractionPlugin
+//==============================================================================
+
+pub(crate) struct SaikoInteractionPlugin;
+
+impl Plugin for SaikoInteractionPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<SaikoPointers>()
+            .add_event::<SaikoPointerEnter>()
+            .add_event::<SaikoPointerLeave>()
+            .add_event::<SaikoPointerDown>()
+            .add_event::<SaikoPointerUp>()
+            .add_event::<SaikoPointerClick>()
+            .add_event::<SaikoPointerScroll>()
+            .add_systems(PreUpdate, (update_pointers, update_interactions).chain().after(InputSystem))
+
+            .register_type::<SaikoInteraction>()
+            .register_type::<SaikoHitRadius>()
+        ;
+    }
+}
+
+//==============================================================================
+//          SaikoInteraction Components
+//==============================================================================
+
+/// Makes the node hittable, and keeps whether it is hovered or pressed by any pointer.
+#[derive(Component, Reflect, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[reflect(Component)]
+pub enum SaikoInteraction {
+    #[default]
+    None,
+    Hovered,
+    Pressed,
+}
+
+/// Rounds the corners of the area the node can be hit in, so the corners of a rounded rect are
+/// not clickable. The radius of each corner is in the order top left, bottom left, top right,
+/// bottom right, like the rect styler.
+#[derive(Component, Reflect, Clone, Copy, Default)]
+#[reflect(Component)]
+pub struct SaikoHitRadius(pub Vec4);
+
+//==============================================================================
+//          SaikoPointers
+//==============================================================================
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum SaikoPointerId {
+    Mouse,
+    Touch(u64),
+}
+
+/// Every pointer that is over a window, and the nodes it is interacting with.
+#[derive(Resource, Default)]
+pub struct SaikoPointers {
+    pointers : HashMap<SaikoPointerId, SaikoPointer>,
+}
+
+impl SaikoPointers {
+    pub fn get(&self, pointer : SaikoPointerId) -> Option<&SaikoPointer> {
+        self.pointers.get(&pointer)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&SaikoPointerId, &SaikoPointer)> {
+        self.pointers.iter()
+    }
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct SaikoPointer {
+    /// The window the pointer is over, and where it is in that window's ui, relative to the center
+    /// of the window with y pointing up.
+    pub location : Option<(Entity, Vec2)>,
+    /// The topmost node under the pointer.
+    pub hovered : Option<Entity>,
+    /// The node that each button was pressed on, until it is released.
+    pub pressed : HashMap<MouseButton, Entity>,
+}
+
+//==============================================================================
+//          SaikoPointer Events
+//==============================================================================
+
+/// The pointer moved over the node.
+#[derive(Event, Clone, Debug)]
+pub struct SaikoPointerEnter {
+    pub entity : Entity,
+    pub pointer : SaikoPointerId,
+}
+
+/// The pointer moved off of the node.
+#[derive(Event, Clone, Debug)]
+pub struct SaikoPointerLeave {
+    pub entity : Entity,
+    pub pointer : SaikoPointerId,
+}
+
+/// A button was pressed over the node. Touches press the left button.
+#[derive(Event, Clone, Debug)]
+pub struct SaikoPointerDown {
+    pub entity : Entity,
+    pub pointer : SaikoPointerId,
+    pub button : MouseButton,
+    pub position : Vec2,
+}
+
+/// A button was released over the node.
+#[derive(Event, Clone, Debug)]
+pub struct SaikoPointerUp {
+    pub entity : Entity,
+    pub pointer : SaikoPointerId,
+    pub button : MouseButton,
+    pub position : Vec2,
+}
+
+/// A button was pressed and released over the same node.
+#[derive(Event, Clone, Debug)]
+pub struct SaikoPointerClick {
+    pub entity : Entity,
+    pub pointer : SaikoPointerId,
+    pub button : MouseButton,
+    pub position : Vec2,
+}
+
+/// The mouse wheel was scrolled over the node.
+#[derive(Event, Clone, Debug)]
+pub struct SaikoPointerScroll {
+    pub entity : Entity,
+    pub pointer : SaikoPointerId,
+    pub unit : MouseScrollUnit,
+    pub delta : Vec2,
+}
+
+#[derive(SystemParam)]
+pub(crate) struct SaikoPointerEvents<'w> {
+    enter : EventWriter<'w, SaikoPointerEnter>,
+    leave : EventWriter<'w, SaikoPointerLeave>,
+    down : EventWriter<'w, SaikoPointerDown>,
+    up : EventWriter<'w, SaikoPointerUp>,
+    click : EventWriter<'w, SaikoPointerClick>,
+}
+
+impl SaikoPointerEvents<'_> {
+    fn hover(&mut self, id : SaikoPointerId, pointer : &mut SaikoPointer, hovered : Option<Entity>) {
+        if pointer.hovered == hovered { return }
+
+        if let Some(entity) = pointer.hovered {
+            self.leave.send(SaikoPointerLeave { entity, pointer : id });
+        }
+        if let Some(entity) = hovered {
+            self.enter.send(SaikoPointerEnter { entity, pointer : id });
+        }
+        pointer.hovered = hovered;
+    }
+
+    fn press(&mut self, id : SaikoPointerId, pointer : &mut SaikoPointer, button : MouseButton) {
+        let (Some(entity), Some((_, position))) = (pointer.hovered, pointer.location) else { return };
+
+        self.down.send(SaikoPointerDown { entity, pointer : id, button, position });
+        pointer.pressed.insert(button, entity);
+    }
+
+    fn release(&mut self, id : SaikoPointerId, pointer : &mut SaikoPointer, button : MouseButton) {
+        let pressed = pointer.pressed.remove(&button);
+        let (Some(entity), Some((_, position))) = (pointer.hovered, pointer.location) else { return };
+
+        self.up.send(SaikoPointerUp { entity, pointer : id, button, position });
+        if pressed == Some(entity) {
+            self.click.send(SaikoPointerClick { entity, pointer : id, button, position });
+        }
+    }
+}
+
+//==============================================================================
+//          SaikoInteraction Systems
+//==============================================================================
+
+pub(crate) fn update_pointers(
+    mut pointers : ResMut<SaikoPointers>,
+    mut events : SaikoPointerEvents,
+    mut scroll_events : EventWriter<SaikoPointerScroll>,
+    mut mouse_wheel : EventReader<MouseWheel>,
+    mouse_buttons : Res<ButtonInput<MouseButton>>,
+    touches : Res<Touches>,
+    windows : Query<(Entity, &Window)>,
+    nodes : Query<(Entity, &SaikoNode, Option<&SaikoHitRadius>, Option<&InheritedVisibility>), With<SaikoInteraction>>,
+    parents : Query<&Parent>,
+    roots : SaikoRoots,
+) {
+    // Only the nodes that can be hit are checked, so it is cheap to find the window of each one.
+    let targets = nodes
+        .iter()
+        .filter(|(_, _, _, visibility)| visibility.map_or(true, |visibility| visibility.get()))
+        .filter_map(|(entity, node, radius, _)| {
+            let window = roots.window(entity)?;
+            let depth = parents.iter_ancestors(entity).count();
+            Some((entity, window, *node.bounds(), radius.map_or(Vec4::ZERO, |radius| radius.0), depth))
+        })
+        .collect::<Vec<_>>();
+
+    let hit_test = |window : Entity, point : Vec2| {
+        targets
+            .iter()
+            .filter(|(_, target_window, bounds, radius, _)| *target_window == window && contains(bounds, *radius, point))
+            .max_by_key(|(_, _, bounds, _, depth)| (bounds.z_index, *depth))
+            .map(|(entity, ..)| *entity)
+    };
+
+    // Window positions have y pointing down from the top left corner, and the ui is laid out from
+    // the center of the window with y pointing up.
+    let to_ui = |window : Entity, position : Vec2| {
+        let (_, window) = windows.get(window).ok()?;
+        Some(Vec2::new(position.x - window.width() * 0.5, window.height() * 0.5 - position.y))
+    };
+
+    //The mouse is only over one window at a time.
+    let mouse_location = windows
+        .iter()
+        .find_map(|(entity, window)| Some((entity, window.cursor_position()?)))
+        .and_then(|(window, position)| Some((window, to_ui(window, position)?)));
+
+    let mouse = pointers.pointers.entry(SaikoPointerId::Mouse).or_default();
+    mouse.location = mouse_location;
+    let hovered = mouse_location.and_then(|(window, point)| hit_test(window, point));
+    events.hover(SaikoPointerId::Mouse, mouse, hovered);
+
+    for button in mouse_buttons.get_just_pressed() {
+        events.press(SaikoPointerId::Mouse, mouse, *button);
+    }
+    for button in mouse_buttons.get_just_released() {
+        events.release(SaikoPointerId::Mouse, mouse, *button);
+    }
+
+    for event in mouse_wheel.read() {
+        let Some(entity) = mouse.hovered else { continue };
+        scroll_events.send(SaikoPointerScroll {
+            entity,
+            pointer : SaikoPointerId::Mouse,
+            unit : event.unit,
+            delta : Vec2::new(event.x, event.y),
+        });
+    }
+
+    //Touches are always in the primary window.
+    let Some(primary_window) = roots.primary_window() else { return };
+
+    for touch in touches.iter().chain(touches.iter_just_released()) {
+        let id = SaikoPointerId::Touch(touch.id());
+        let pointer = pointers.pointers.entry(id).or_default();
+        pointer.location = to_ui(primary_window, touch.position()).map(|point| (primary_window, point));
+        let hovered = pointer.location.and_then(|(window, point)| hit_test(window, point));
+        events.hover(id, pointer, hovered);
+
+        if touches.just_pressed(touch.id()) {
+            events.press(id, pointer, MouseButton::Left);
+        }
+        if touches.just_released(touch.id()) {
+            events.release(id, pointer, MouseButton::Left);
+        }
+    }
+
+    // Touches that were lifted or canceled leave the node they were over.
+    let lifted = pointers
+        .pointers
+        .keys()
+        .filter(|id| match id {
+            SaikoPointerId::Touch(touch) => touches.get_pressed(*touch).is_none(),
+            SaikoPointerId::Mouse => false,
+        })
+        .copied()
+        .collect::<Vec<_>>();
+    for id in lifted {
+        let Some(mut pointer) = pointers.pointers.remove(&id) else { continue };
+        events.hover(id, &mut pointer, None);
+    }
+}
+
+/// Only writes the interactions that changed, so components that restyle on change are not
+/// redrawn every frame.
+fn update_interactions(
+    pointers : Res<SaikoPointers>,
+    mut interactions : Query<(Entity, &mut SaikoInteraction)>,
+) {
+    for (entity, mut interaction) in interactions.iter_mut() {
+        let is_pressed = pointers.iter().any(|(_, pointer)| pointer.pressed.values().any(|pressed| *pressed == entity));
+        let is_hovered = pointers.iter().any(|(_, pointer)| pointer.hovered == Some(entity));
+
+        let state = match (is_pressed, is_hovered) {
+            (true, _) => SaikoInteraction::Pressed,
+            (false, true) => SaikoInteraction::Hovered,
+            (false, false) => SaikoInteraction::None,
+        };
+        interaction.set_if_neq(state);
+    }
+}
+
+/// Whether the point is inside of the bounds, with the corners rounded off by the radius.
+fn contains(bounds : &Bounds, radius : Vec4, point : Vec2) -> bool {
+    // Flipped so y points down, which is the order the radius is in.
+    let p = (point - bounds.center) * Vec2::new(1.0, -1.0);
+    let r = if p.x > 0.0 { radius.zw() } else { radius.xy() };
+    let r = if p.y > 0.0 { r.y } else { r.x }.min(bounds.size.min_element());
+    let q = p.abs() - bounds.size + r;
+    q.x.max(q.y).min(0.0) + q.max(Vec2::ZERO).length() - r <= 0.0
+}
@@ -1,12 +1,21 @@
 pub mod component;
 pub mod node;
 pub mod context;
+pub mod drag;
+pub mod flex;
+pub mod focus;
+pub mod interaction;
+pub mod navigation;
 pub mod position;
 
 use bevy::prelude::*;
 
 use self::{
     component::{rect::RectComponent, SaikoComponentPlugin},
+    drag::SaikoDragPlugin,
+    focus::SaikoFocusPlugin,
+    interaction::SaikoInteractionPlugin,
+    navigation::SaikoNavigationPlugin,
     node::SaikoNodePlugin,
 };
 
@@ -17,6 +26,10 @@ impl Plugin for SaikoUiPlugin {
         app
             .add_plugins(SaikoComponentPlugin::<RectComponent>::default())
             .add_plugins(SaikoNodePlugin)
+            .add_plugins(SaikoInteractionPlugin)
+            .add_plugins(SaikoFocusPlugin)
+            .add_plugins(SaikoNavigationPlugin)
+            .add_plugins(SaikoDragPlugin)
             .register_type::<RectComponent>()
         ;
     }
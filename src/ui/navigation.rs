@@ -0,0 +1,191 @@
+//==============================================================================
+//  Navigation moves the focus between nodes with the arrow keys and the
+//  d-pad. The next node is picked from the laid out bounds, preferring nodes
+//  that line up with the focused node in the direction of travel, so menus
+//  work without a mouse or any setup. Nodes can still name their neighbors
+//  when the layout is not enough.
+//==============================================================================
+
+use bevy::{input::InputSystem, prelude::*};
+
+use crate::common::bounds::Bounds;
+
+use super::{
+    focus::{navigate_focus, Focusable, SaikoFocus, SaikoFocusSubmit},
+    node::{SaikoNode, SaikoRoots},
+};
+
+//==============================================================================
+//          SaikoNavigationPlugin
+//==============================================================================
+
+pub(crate) struct SaikoNavigationPlugin;
+
+impl Plugin for SaikoNavigationPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<SaikoNavigate>()
+            .add_systems(PreUpdate, (send_navigation_input, navigate_spatially).chain().after(InputSystem).after(navigate_focus))
+
+            .register_type::<SaikoNavNeighbors>()
+        ;
+    }
+}
+
+//==============================================================================
+//          SaikoNavigation Components
+//==============================================================================
+
+#[derive(Reflect, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SaikoNavDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// Overrides which node is focused when navigating away from this node. Directions that are
+/// `None` are picked from the layout.
+#[derive(Component, Reflect, Clone, Copy, Default)]
+#[reflect(Component)]
+pub struct SaikoNavNeighbors {
+    pub up : Option<Entity>,
+    pub down : Option<Entity>,
+    pub left : Option<Entity>,
+    pub right : Option<Entity>,
+}
+
+impl SaikoNavNeighbors {
+    pub fn get(&self, direction : SaikoNavDirection) -> Option<Entity> {
+        match direction {
+            SaikoNavDirection::Up => self.up,
+            SaikoNavDirection::Down => self.down,
+            SaikoNavDirection::Left => self.left,
+            SaikoNavDirection::Right => self.right,
+        }
+    }
+}
+
+//==============================================================================
+//          SaikoNavigate Event
+//==============================================================================
+
+/// Moves the focus to the next node in the direction. The arrow keys and the d-pad send this, and
+/// it can be sent directly for other bindings.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct SaikoNavigate {
+    pub direction : SaikoNavDirection,
+}
+
+//==============================================================================
+//          SaikoNavigation Systems
+//==============================================================================
+
+/// The south button of a gamepad submits the focused node, like Enter does on the keyboard.
+fn send_navigation_input(
+    mut navigate : EventWriter<SaikoNavigate>,
+    mut submit : EventWriter<SaikoFocusSubmit>,
+    focus : Res<SaikoFocus>,
+    keys : Res<ButtonInput<KeyCode>>,
+    gamepad_buttons : Res<ButtonInput<GamepadButton>>,
+) {
+    let keys = keys
+        .get_just_pressed()
+        .filter_map(|key| match key {
+            KeyCode::ArrowUp => Some(SaikoNavDirection::Up),
+            KeyCode::ArrowDown => Some(SaikoNavDirection::Down),
+            KeyCode::ArrowLeft => Some(SaikoNavDirection::Left),
+            KeyCode::ArrowRight => Some(SaikoNavDirection::Right),
+            _ => None,
+        });
+
+    let d_pad = gamepad_buttons
+        .get_just_pressed()
+        .filter_map(|button| match button.button_type {
+            GamepadButtonType::DPadUp => Some(SaikoNavDirection::Up),
+            GamepadButtonType::DPadDown => Some(SaikoNavDirection::Down),
+            GamepadButtonType::DPadLeft => Some(SaikoNavDirection::Left),
+            GamepadButtonType::DPadRight => Some(SaikoNavDirection::Right),
+            _ => None,
+        });
+
+    for direction in keys.chain(d_pad) {
+        navigate.send(SaikoNavigate { direction });
+    }
+
+    let Some(entity) = focus.focused() else { return };
+    let submitted = gamepad_buttons
+        .get_just_pressed()
+        .any(|button| button.button_type == GamepadButtonType::South);
+    if submitted {
+        submit.send(SaikoFocusSubmit { entity });
+    }
+}
+
+fn navigate_spatially(
+    mut focus : ResMut<SaikoFocus>,
+    mut navigate : EventReader<SaikoNavigate>,
+    nodes : Query<(Entity, &SaikoNode, &Focusable, Option<&InheritedVisibility>)>,
+    neighbors : Query<&SaikoNavNeighbors>,
+    roots : SaikoRoots,
+) {
+    for event in navigate.read() {
+        let candidates = nodes
+            .iter()
+            .filter(|(_, _, focusable, visibility)| focusable.tab_index >= 0 && visibility.map_or(true, |v| v.get()))
+            .map(|(entity, node, _, _)| (entity, *node.bounds()));
+
+        let Some((focused, bounds)) = focus.focused().and_then(|focused| nodes.get(focused).ok()).map(|(entity, node, _, _)| (entity, *node.bounds())) else {
+            // Without a focused node, navigating starts at the top left node.
+            let first = candidates.min_by(|(_, a), (_, b)| {
+                let (a_top, b_top) = (a.center.y + a.size.y, b.center.y + b.size.y);
+                let (a_left, b_left) = (a.center.x - a.size.x, b.center.x - b.size.x);
+                b_top.total_cmp(&a_top).then(a_left.total_cmp(&b_left))
+            });
+            if let Some((entity, _)) = first {
+                focus.focus(entity);
+            }
+            continue;
+        };
+
+        let neighbor = neighbors.get(focused).ok().and_then(|neighbors| neighbors.get(event.direction));
+        if let Some(neighbor) = neighbor {
+            focus.focus(neighbor);
+            continue;
+        }
+
+        // Only the nodes in the same window can be navigated to.
+        let window = roots.window(focused);
+        let next = candidates
+            .filter(|(entity, _)| *entity != focused && roots.window(*entity) == window)
+            .filter_map(|(entity, candidate)| Some((entity, navigation_score(&bounds, &candidate, event.direction)?)))
+            .min_by(|(_, (a_tier, a)), (_, (b_tier, b))| a_tier.cmp(b_tier).then(a.total_cmp(b)));
+
+        if let Some((entity, _)) = next {
+            focus.focus(entity);
+        }
+    }
+}
+
+/// How far the candidate is from the bounds in the direction, or `None` if it is not in that
+/// direction. Candidates that overlap the bounds on the other axis always win over candidates
+/// that don't, so moving down a column never jumps to a closer node in the next column.
+fn navigation_score(bounds : &Bounds, candidate : &Bounds, direction : SaikoNavDirection) -> Option<(u32, f32)> {
+    let (min, max) = (bounds.center - bounds.size, bounds.center + bounds.size);
+    let (candidate_min, candidate_max) = (candidate.center - candidate.size, candidate.center + candidate.size);
+
+    // The ui is laid out with y pointing up.
+    let (is_ahead, distance, range, candidate_range) = match direction {
+        SaikoNavDirection::Up => (candidate.center.y > bounds.center.y, candidate_min.y - max.y, (min.x, max.x), (candidate_min.x, candidate_max.x)),
+        SaikoNavDirection::Down => (candidate.center.y < bounds.center.y, min.y - candidate_max.y, (min.x, max.x), (candidate_min.x, candidate_max.x)),
+        SaikoNavDirection::Right => (candidate.center.x > bounds.center.x, candidate_min.x - max.x, (min.y, max.y), (candidate_min.y, candidate_max.y)),
+        SaikoNavDirection::Left => (candidate.center.x < bounds.center.x, min.x - candidate_max.x, (min.y, max.y), (candidate_min.y, candidate_max.y)),
+    };
+    if !is_ahead { return None }
+
+    // How far apart the two are on the other axis, which is 0 when they overlap.
+    let gap = (candidate_range.0 - range.1).max(range.0 - candidate_range.1).max(0.0);
+    let tier = if gap > 0.0 { 1 } else { 0 };
+
+    Some((tier, distance.max(0.0) + gap * 2.0))
+}
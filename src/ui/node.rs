@@ -4,11 +4,11 @@
 //  be updated or not.
 //==============================================================================
 
-use bevy::{prelude::*, utils::HashSet, window::PrimaryWindow};
+use bevy::{ecs::system::SystemParam, prelude::*, render::camera::CameraUpdateSystem, utils::{HashMap, HashSet}, window::{PrimaryWindow, WindowResized}};
 
-use crate::common::{bounds::Bounds, util::get_all_children};
+use crate::{common::{bounds::Bounds, util::{find_ancestor, get_all_children}}, render::{camera_window, image_target::SaikoImageTarget, SaikoCamera, SaikoWindowRoot}};
 
-use super::position::RelativePosition;
+use super::{flex::{layout_flex, SaikoFlexContainer, SaikoFlexItem}, position::RelativePosition};
 
 //==============================================================================
 //          SaikoNodePlugin
@@ -19,9 +19,11 @@ pub(crate) struct SaikoNodePlugin;
 impl Plugin for SaikoNodePlugin {
     fn build(&self, app: &mut App) {
         app
-            .add_systems(PostUpdate, update_node_bounds)
+            .add_systems(PostUpdate, (warn_shared_viewports, update_node_bounds).chain().after(CameraUpdateSystem))
             
             .register_type::<SaikoNode>()
+            .register_type::<SaikoFlexContainer>()
+            .register_type::<SaikoFlexItem>()
         ;
         
     }
@@ -38,6 +40,7 @@ pub struct SaikoNode {
     bounds: Bounds,
     #[reflect(default)]
     position: RelativePosition,
+    z_index: i32,
     is_dirty: bool,
 }
 
@@ -46,9 +49,25 @@ impl SaikoNode {
         SaikoNode {
             bounds: Bounds::default(),
             position,
+            z_index: 0,
             is_dirty: true,
         }
     }
+    
+    /// Raises the node above its parent. The z_index is relative to the parent node, so a popup
+    /// with a z_index of 10 will always draw over its siblings with a lower z_index.
+    pub fn with_z_index(mut self, z_index: i32) -> Self {
+        self.z_index = z_index;
+        self
+    }
+    
+    pub fn z_index(&self) -> i32 {
+        self.z_index
+    }
+    
+    pub fn set_z_index(&mut self, z_index: i32) {
+        self.z_index = z_index;
+    }
 
     pub fn bounds(&self) -> &Bounds {
         &self.bounds
@@ -56,6 +75,13 @@ impl SaikoNode {
     
     pub fn calc_bounds(&mut self, parent: &Bounds) {
         self.position.calc_bounds(parent, &mut self.bounds);
+        self.bounds.z_index += self.z_index;
+    }
+    
+    /// Sets the bounds that a layout picked for the node, like the flex layout of its parent.
+    pub(crate) fn set_bounds(&mut self, bounds: Bounds) {
+        self.bounds = bounds;
+        self.bounds.z_index += self.z_index;
     }
 }
 
@@ -64,20 +90,34 @@ impl SaikoNode {
 //==============================================================================
 
 fn update_node_bounds(
-    mut nodes: Query<(Entity, &mut SaikoNode, Option<&Children>, Option<&Parent>), Changed<SaikoNode>>,
-    primary_window : Query<&Window, With<PrimaryWindow>>
+    mut nodes: Query<(Entity, &mut SaikoNode, Option<&Children>, Option<&Parent>)>,
+    changed_nodes: Query<(Entity, Option<&Parent>), (With<SaikoNode>, Or<(Changed<SaikoNode>, Changed<SaikoFlexContainer>, Changed<SaikoFlexItem>, Changed<Children>)>)>,
+    flex_containers : Query<&SaikoFlexContainer>,
+    flex_items : Query<&SaikoFlexItem>,
+    changed_root_containers : Query<Entity, (With<SaikoFlexContainer>, Without<SaikoNode>, Or<(Changed<SaikoFlexContainer>, Changed<Children>)>)>,
+    children : Query<&Children>,
+    mut window_resized : EventReader<WindowResized>,
+    roots : SaikoRoots,
 ) {
-    if nodes.iter().next().is_none() { return }
-    println!("Updating Node Bounds");
-    
-    let Ok(window) = primary_window.get_single() else { return };
-    // let window_bounds = Bounds::new(Vec2::ZERO, Vec2::new(window.width(), window.height()), 0);
-    
-    let entities_to_update = nodes
-        .iter()
-        .map(|query| query.0)
-        .collect::<Vec<_>>();
+    // A camera changes when its viewport or its window is resized, which moves every root. Windows
+    // are checked too, since a window root can be laid out without a camera.
+    let viewport_changed = window_resized.read().count() > 0 || roots.cameras.iter().any(|(camera, _)| camera.is_changed());
+    if changed_nodes.is_empty() && changed_root_containers.is_empty() && !viewport_changed { return }
+    trace!("Updating Node Bounds");
     
+    // A flex item moves its siblings, so every item in the container is laid out again. The
+    // container can be a root that is not a node, so the siblings are updated instead of it.
+    let entities_to_update = match viewport_changed {
+        true => nodes.iter().map(|query| query.0).collect::<Vec<_>>(),
+        false => changed_nodes
+            .iter()
+            .flat_map(|(entity, parent)| match parent {
+                Some(parent) if flex_containers.contains(**parent) => node_children(**parent, &children, &nodes),
+                _ => vec![entity],
+            })
+            .chain(changed_root_containers.iter().flat_map(|container| node_children(container, &children, &nodes)))
+            .collect::<Vec<_>>(),
+    };
     
     let families_to_update = entities_to_update
         .iter()
@@ -85,22 +125,149 @@ fn update_node_bounds(
         .collect::<Vec<_>>();
     
     let mut updated_nodes = HashSet::new();
-    
+    let mut flex_bounds = HashMap::new();
     for family in families_to_update {
         for entity in family {
             if !updated_nodes.insert(entity) || !nodes.contains(entity) { continue }
             
-            let mut parent_bounds = Bounds::new(Vec2::ZERO, Vec2::new(window.width(), window.height()), 0);
-            
             let parent = nodes.get(entity).map(|t| t.3.map(|parent| **parent)).unwrap_or(None);
-            if let Some(parent) = parent {
-                if let Ok((_, parent_node, _, _)) = nodes.get_mut(parent) {
-                    parent_bounds = parent_node.bounds;
+            let parent_bounds = match parent.and_then(|parent| nodes.get(parent).ok()) {
+                Some((_, parent_node, _, _)) => parent_node.bounds,
+                None => {
+                    let Some(root_bounds) = roots.bounds(entity) else { continue };
+                    root_bounds
+                },
+            };
+            
+            // Parents are laid out before their children, so the first item of a container lays
+            // out every item in it.
+            let container = parent
+                .filter(|_| flex_items.contains(entity))
+                .and_then(|parent| Some((parent, flex_containers.get(parent).ok()?)));
+            if let Some((parent, container)) = container {
+                if !flex_bounds.contains_key(&entity) {
+                    let (items, item_entities) : (Vec<_>, Vec<_>) = node_children(parent, &children, &nodes)
+                        .into_iter()
+                        .filter_map(|child| Some((*flex_items.get(child).ok()?, child)))
+                        .unzip();
+                    flex_bounds.extend(item_entities.into_iter().zip(layout_flex(container, &parent_bounds, &items)));
                 }
             }
             
             let Ok((_, mut node, _, _)) = nodes.get_mut(entity) else { continue };
-            node.calc_bounds(&parent_bounds);
+            match flex_bounds.get(&entity) {
+                Some(bounds) => node.set_bounds(*bounds),
+                None => node.calc_bounds(&parent_bounds),
+            }
         }
     }    
 }
+
+/// The children of the entity that are nodes.
+fn node_children(parent : Entity, children : &Query<&Children>, nodes : &Query<(Entity, &mut SaikoNode, Option<&Children>, Option<&Parent>)>) -> Vec<Entity> {
+    children
+        .get(parent)
+        .map(|children| children.iter().copied().filter(|child| nodes.contains(*child)).collect())
+        .unwrap_or_default()
+}
+
+/// Nodes without a root can only be laid out in one viewport, so cameras without a root that
+/// share a window would draw ui that was laid out for another camera.
+fn warn_shared_viewports(
+    changed_cameras : Query<(), Changed<SaikoCamera>>,
+    roots : SaikoRoots,
+) {
+    if changed_cameras.is_empty() { return }
+    
+    let primary_window = roots.primary_window();
+    let mut windows = HashSet::new();
+    for (camera, _) in roots.cameras.iter().filter(|(_, saiko_camera)| saiko_camera.root.is_none()) {
+        let Some(window) = camera_window(&camera, primary_window) else { continue };
+        if !windows.insert(window) {
+            warn!("Several SaikoCameras without a root are in window {:?}. Their ui is laid out in the viewport of only one of them, so give each camera its own root.", window);
+        }
+    }
+}
+
+/// Everything that a node without a parent node can be laid out in.
+#[derive(SystemParam)]
+pub(crate) struct SaikoRoots<'w, 's> {
+    parents : Query<'w, 's, &'static Parent>,
+    cameras : Query<'w, 's, (Ref<'static, Camera>, &'static SaikoCamera)>,
+    windows : Query<'w, 's, &'static Window>,
+    primary_window : Query<'w, 's, Entity, With<PrimaryWindow>>,
+    window_roots : Query<'w, 's, &'static SaikoWindowRoot>,
+    image_targets : Query<'w, 's, &'static SaikoImageTarget>,
+    images : Res<'w, Assets<Image>>,
+}
+
+impl SaikoRoots<'_, '_> {
+    /// The bounds that a node without a parent node is laid out in, in logical pixels. The nodes
+    /// of an image target are laid out in the image, the nodes under a window root in that window,
+    /// and the nodes under a camera's root in that camera's viewport. Every other node is laid out
+    /// in the viewport of the first camera without a root in the primary window, or the primary
+    /// window itself if there is none.
+    fn bounds(&self, entity : Entity) -> Option<Bounds> {
+        let root = self.root(entity);
+        
+        if let Some(image_target) = root.and_then(|root| self.image_targets.get(root).ok()) {
+            let image = self.images.get(&image_target.image)?;
+            return Some(Bounds::new(Vec2::ZERO, image.size_f32() * 0.5, 0));
+        }
+        
+        if let Some(window_root) = root.and_then(|root| self.window_roots.get(root).ok()) {
+            return self.window_bounds(window_root.window);
+        }
+        
+        let primary_window = self.primary_window();
+        let camera = self.cameras
+            .iter()
+            .filter(|(camera, _)| root.is_some() || camera_window(camera, primary_window) == primary_window)
+            .find(|(_, saiko_camera)| saiko_camera.root == root)
+            .and_then(|(camera, _)| viewport_bounds(&camera));
+        
+        camera.or_else(|| self.window_bounds(primary_window?))
+    }
+    
+    /// The closest image target, window root or camera root above the node, if it has one.
+    pub(crate) fn root(&self, entity : Entity) -> Option<Entity> {
+        find_ancestor(entity, &self.parents, |entity| {
+            self.image_targets.contains(entity)
+                || self.window_roots.contains(entity)
+                || self.cameras.iter().any(|(_, saiko_camera)| saiko_camera.root == Some(entity))
+        })
+    }
+    
+    pub(crate) fn primary_window(&self) -> Option<Entity> {
+        self.primary_window.get_single().ok()
+    }
+    
+    /// The window that the node is drawn in, or `None` when it is drawn into an image.
+    pub(crate) fn window(&self, entity : Entity) -> Option<Entity> {
+        let primary_window = self.primary_window();
+        let Some(root) = self.root(entity) else { return primary_window };
+        
+        if let Ok(window_root) = self.window_roots.get(root) {
+            return Some(window_root.window);
+        }
+        
+        self.cameras
+            .iter()
+            .find(|(_, saiko_camera)| saiko_camera.root == Some(root))
+            .and_then(|(camera, _)| camera_window(&camera, primary_window))
+    }
+    
+    fn window_bounds(&self, window : Entity) -> Option<Bounds> {
+        let window = self.windows.get(window).ok()?;
+        Some(Bounds::new(Vec2::ZERO, Vec2::new(window.width(), window.height()) * 0.5, 0))
+    }
+}
+
+/// The viewport of the camera, relative to the center of the texture the camera renders to with
+/// y pointing up. The ui is drawn over the whole texture, so split screen viewports are off center.
+fn viewport_bounds(camera : &Camera) -> Option<Bounds> {
+    let target_size = camera.logical_target_size()?;
+    let viewport = camera.logical_viewport_rect()?;
+    let center = viewport.center() - target_size * 0.5;
+    Some(Bounds::new(Vec2::new(center.x, -center.y), viewport.half_size(), 0))
+}
@@ -73,11 +73,14 @@ impl RelativePosition {
         
         child.center = Vec2::new(x, y);
         child.size = Vec2::new(width, height);
+        child.z_index = parent.z_index;
     }
     
+    /// The z_index of `bounds` is relative to the parent, like its center.
     pub fn calc_relative(parent: &Bounds, child: &mut Bounds, bounds: &Bounds) {
         child.center = parent.center + bounds.center;
         child.size = bounds.size;
+        child.z_index = parent.z_index + bounds.z_index;
     }
     
     pub fn create_relative(parent: &Bounds, bounds: &Bounds) -> Bounds {